@@ -0,0 +1,29 @@
+use geometry::{Bounded,Hit,Viewable};
+use ray::Ray;
+use octree::Octree;
+use bvh::Bvh;
+use std::fmt::Debug;
+
+/// Structure that speeds up finding what a ray hits among many primitives.
+pub trait Accelerator: Bounded + Send + Sync {
+    /// Closest hit along `ray` between `t_min` and `t_max`.
+    fn intersect(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit>;
+
+    /// Whether anything blocks `ray` between `t_min` and `t_max`.
+    fn occluded(&self, ray: Ray, t_min: f64, t_max: f64) -> bool;
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AcceleratorKind {
+    Octree,
+    Bvh,
+}
+
+pub fn build<T, I>(kind: AcceleratorKind, items: I) -> Box<dyn Accelerator>
+    where T: Bounded + Viewable + Clone + Debug + Send + Sync + 'static, I: IntoIterator<Item=T>
+{
+    match kind {
+        AcceleratorKind::Octree => Box::new(items.into_iter().collect::<Octree<T>>()),
+        AcceleratorKind::Bvh => Box::new(items.into_iter().collect::<Bvh<T>>()),
+    }
+}
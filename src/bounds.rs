@@ -1,6 +1,6 @@
 use std::ops::Add;
 
-use geometry::{Bounded, Viewable};
+use geometry::Bounded;
 use ray::Ray;
 use point::Point;
 
@@ -25,12 +25,12 @@ impl Bounds {
         zmax: f64,
     ) -> Bounds {
         Bounds {
-            xmin: xmin,
-            xmax: xmax,
-            ymin: ymin,
-            ymax: ymax,
-            zmin: zmin,
-            zmax: zmax,
+            xmin,
+            xmax,
+            ymin,
+            ymax,
+            zmin,
+            zmax,
         }
     }
 
@@ -50,6 +50,24 @@ impl Bounds {
         self.zmax - self.zmin
     }
 
+    pub fn surface_area(self) -> f64 {
+        let (w, d, h) = (self.width(), self.depth(), self.height());
+        2.0 * (w * d + w * h + d * h)
+    }
+
+    pub fn center(self) -> Point {
+        Point::new(
+            (self.xmin + self.xmax) / 2.0,
+            (self.ymin + self.ymax) / 2.0,
+            (self.zmin + self.zmax) / 2.0,
+        )
+    }
+
+    /// Radius of the smallest sphere around `center` containing the bounds.
+    pub fn radius(self) -> f64 {
+        Point::new(self.xmin, self.ymin, self.zmin).distance_to(self.center())
+    }
+
     pub fn overlaps(self, other: Bounds) -> bool {
         if self.xmax < other.xmin { return false; }
         if self.xmin > other.xmax { return false; }
@@ -78,40 +96,92 @@ impl Add for Bounds {
 
 impl Bounded for Bounds {
     fn bounds(&self) -> Bounds {
-        self.clone()
+        *self
     }
 }
 
-impl Viewable for Bounds {
-    fn intersects(&self, r: Ray) -> Option<Point> {
-        let _txmin = (self.xmin - r.loc.x) / r.dir.x;
-        let _txmax = (self.xmax - r.loc.x) / r.dir.x;
-        let (mut txmin, mut txmax) = if _txmin > _txmax { (_txmax, _txmin) } else { (_txmin, _txmax) };
+impl Bounds {
+
+    /// Distances along `r` where it enters and leaves the box. The entry
+    /// distance is negative when the ray starts inside, and boxes entirely
+    /// behind the ray are missed.
+    pub fn intersects(&self, r: Ray) -> Option<(f64, f64)> {
+        let (txmin, txmax) = slab(self.xmin, self.xmax, r.loc.x, r.inv.x)?;
+        let (tymin, tymax) = slab(self.ymin, self.ymax, r.loc.y, r.inv.y)?;
+        let (tzmin, tzmax) = slab(self.zmin, self.zmax, r.loc.z, r.inv.z)?;
 
-        let _tymin = (self.ymin - r.loc.y) / r.dir.y;
-        let _tymax = (self.ymax - r.loc.y) / r.dir.y;
-        let (mut tymin, mut tymax) = if _tymin > _tymax { (_tymax, _tymin) } else { (_tymin, _tymax) };
+        let enter = txmin.max(tymin).max(tzmin);
+        let exit = txmax.min(tymax).min(tzmax);
 
-        if (txmin > tymax) || (tymin > txmax) {
+        if enter > exit || exit < 0.0 {
             return None;
         }
 
-        if tymin > txmin {
-            txmin = tymin;
-        }
+        Some((enter, exit))
+    }
+}
 
-        if tymax < txmax {
-            txmax = tymax;
-        }
+/// Distances where a ray crosses the planes `min` and `max` along one axis.
+fn slab(min: f64, max: f64, loc: f64, inv: f64) -> Option<(f64, f64)> {
+    // parallel to the planes, so it's either always or never between them
+    if inv.is_infinite() {
+        return if loc < min || loc > max {
+            None
+        } else {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        };
+    }
 
-        let _tzmin = (self.zmin - r.loc.z) / r.dir.z;
-        let _tzmax = (self.zmax - r.loc.z) / r.dir.z;
-        let (mut tzmin, mut tzmax) = if _tzmin > _tzmax { (_tzmax, _tzmin) } else { (_tzmin, _tzmax) };
+    let t0 = (min - loc) * inv;
+    let t1 = (max - loc) * inv;
+    Some(if t0 < t1 { (t0, t1) } else { (t1, t0) })
+}
 
-        if (txmin > tzmax) || (tzmin > txmax) {
-            return None;
-        }
+#[cfg(test)]
+mod test {
+    use bounds::Bounds;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_intersects() {
+        let b = Bounds::new(0.0, 1.0, 0.0, 1.0, 0.0, 1.0);
+
+        let ray = Ray::new(Point::new(-1.0, 0.5, 0.5), Vector::new(2.0, 0.0, 0.0));
+        assert!(b.intersects(ray) == Some((1.0, 2.0)));
+
+        // diagonal through opposite corners
+        let ray = Ray::new(Point::new(-1.0, -1.0, -1.0), Vector::new(1.0, 1.0, 1.0));
+        let (enter, exit) = b.intersects(ray).unwrap();
+        assert!((enter - 3.0f64.sqrt()).abs() < 1e-9);
+        assert!((exit - 2.0 * 3.0f64.sqrt()).abs() < 1e-9);
+
+        // passes beside the box in z only
+        let ray = Ray::new(Point::new(-1.0, -1.0, 2.0), Vector::new(1.0, 1.0, 0.1));
+        assert!(b.intersects(ray).is_none());
+    }
+
+    #[test]
+    fn test_inside_and_behind() {
+        let b = Bounds::new(0.0, 1.0, 0.0, 1.0, 0.0, 1.0);
+
+        let ray = Ray::new(Point::new(0.5, 0.5, 0.5), Vector::new(0.0, 0.0, 1.0));
+        assert!(b.intersects(ray) == Some((-0.5, 0.5)));
+
+        let ray = Ray::new(Point::new(0.5, 0.5, 2.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(b.intersects(ray).is_none());
+    }
+
+    #[test]
+    fn test_axis_parallel() {
+        let b = Bounds::new(0.0, 1.0, 0.0, 1.0, 0.0, 1.0);
+
+        // running exactly along a face
+        let ray = Ray::new(Point::new(0.0, 0.5, -1.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(b.intersects(ray) == Some((1.0, 2.0)));
 
-        Some(Point::zero())
+        let ray = Ray::new(Point::new(1.5, 0.5, -1.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(b.intersects(ray).is_none());
     }
 }
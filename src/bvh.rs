@@ -0,0 +1,309 @@
+use std::iter::FromIterator;
+
+use geometry::{Bounded,Hit,Viewable};
+use accelerator::Accelerator;
+use bounds::Bounds;
+use point::Point;
+use ray::Ray;
+
+/// Leaves are made once this few items are left, without trying to split.
+const LEAF_SIZE: usize = 2;
+
+/// Past this depth everything left goes into one leaf, which keeps the
+/// traversal stack a fixed size.
+const MAX_DEPTH: usize = 60;
+
+/// Number of buckets centroids are sorted into when estimating splits.
+const BUCKETS: usize = 12;
+
+/// Cost of visiting a node relative to intersecting one item.
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    bounds: Bounds,
+    /// First item of a leaf, or for interior nodes the index of the second
+    /// child. The first child always directly follows its parent.
+    offset: usize,
+    /// Number of items in a leaf, zero for interior nodes.
+    count: usize,
+    /// Axis interior nodes are split along.
+    axis: usize,
+}
+
+/// Bounding volume hierarchy, split using the surface area heuristic and
+/// stored depth first in a flat array.
+#[derive(Debug)]
+pub struct Bvh<T> where T: Bounded + Viewable {
+    nodes: Vec<Node>,
+    items: Vec<T>,
+}
+
+#[derive(Copy, Clone)]
+struct Item {
+    index: usize,
+    bounds: Bounds,
+    center: Point,
+}
+
+impl<T> Bvh<T> where T: Bounded + Viewable {
+
+    pub fn new(items: Vec<T>) -> Bvh<T> {
+        let mut info: Vec<Item> = items.iter().enumerate().map(|(i, item)| {
+            let bounds = item.bounds();
+            Item {
+                index: i,
+                bounds,
+                center: bounds.center(),
+            }
+        }).collect();
+
+        let mut nodes = vec![];
+        if !info.is_empty() {
+            build(&mut nodes, &mut info, 0, 0);
+        }
+
+        // store items in the order the leaves reference them
+        let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        let items = info.iter().map(|item| slots[item.index].take().unwrap()).collect();
+
+        Bvh {
+            nodes,
+            items,
+        }
+    }
+}
+
+fn axis(p: Point, axis: usize) -> f64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+fn bucket(item: &Item, centers: Bounds, dim: usize) -> usize {
+    let min = axis(Point::new(centers.xmin, centers.ymin, centers.zmin), dim);
+    let max = axis(Point::new(centers.xmax, centers.ymax, centers.zmax), dim);
+    let b = ((axis(item.center, dim) - min) / (max - min) * BUCKETS as f64) as usize;
+    b.min(BUCKETS - 1)
+}
+
+/// Total count and bounds of a run of buckets.
+fn merge(counts: &[usize], boxes: &[Option<Bounds>]) -> (usize, Option<Bounds>) {
+    let n = counts.iter().sum();
+    let bounds = boxes.iter().fold(None, |sum: Option<Bounds>, b| match (sum, *b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    });
+
+    (n, bounds)
+}
+
+/// Builds the subtree for `items`, which start at `start` in the final
+/// item order, and returns the index of its root node.
+fn build(nodes: &mut Vec<Node>, items: &mut [Item], start: usize, depth: usize) -> usize {
+    let bounds = items.iter().skip(1).fold(items[0].bounds, |sum, item| sum + item.bounds);
+    let index = nodes.len();
+
+    nodes.push(Node {
+        bounds,
+        offset: start,
+        count: items.len(),
+        axis: 0,
+    });
+
+    if items.len() <= LEAF_SIZE || depth >= MAX_DEPTH {
+        return index;
+    }
+
+    let first = items[0].center;
+    let centers = items.iter().fold(Bounds::new(first.x, first.x, first.y, first.y, first.z, first.z), |sum, item| {
+        let c = item.center;
+        sum + Bounds::new(c.x, c.x, c.y, c.y, c.z, c.z)
+    });
+
+    let extents = [centers.width(), centers.depth(), centers.height()];
+    let dim = (0..3).fold(0, |best, i| if extents[i] > extents[best] { i } else { best });
+
+    // every item is centered on the same point, nothing to split on
+    if extents[dim] == 0.0 {
+        return index;
+    }
+
+    let mut counts = [0usize; BUCKETS];
+    let mut boxes: [Option<Bounds>; BUCKETS] = [None; BUCKETS];
+    for item in items.iter() {
+        let b = bucket(item, centers, dim);
+        counts[b] += 1;
+        boxes[b] = Some(boxes[b].map_or(item.bounds, |sum| sum + item.bounds));
+    }
+
+    // cost of splitting after each bucket, relative to intersecting every item
+    let area = bounds.surface_area();
+    let (split, cost) = (0..BUCKETS - 1).fold((0, f64::INFINITY), |best, i| {
+        let (nl, bl) = merge(&counts[..i + 1], &boxes[..i + 1]);
+        let (nr, br) = merge(&counts[i + 1..], &boxes[i + 1..]);
+
+        match (bl, br) {
+            (Some(bl), Some(br)) => {
+                let cost = TRAVERSAL_COST
+                    + (nl as f64 * bl.surface_area() + nr as f64 * br.surface_area()) / area;
+                if cost < best.1 { (i, cost) } else { best }
+            },
+            _ => best,
+        }
+    });
+
+    if cost >= items.len() as f64 {
+        return index;
+    }
+
+    items.sort_by_key(|item| bucket(item, centers, dim) > split);
+    let mid = items.iter().position(|item| bucket(item, centers, dim) > split).unwrap();
+
+    nodes[index].count = 0;
+    nodes[index].axis = dim;
+
+    build(nodes, &mut items[..mid], start, depth + 1);
+    let second = build(nodes, &mut items[mid..], start + mid, depth + 1);
+    nodes[index].offset = second;
+
+    index
+}
+
+impl<T> Bounded for Bvh<T> where T: Bounded + Viewable {
+    fn bounds(&self) -> Bounds {
+        match self.nodes.first() {
+            Some(node) => node.bounds,
+            None => Bounds::zero(),
+        }
+    }
+}
+
+impl<T> Accelerator for Bvh<T> where T: Bounded + Viewable + Send + Sync {
+    fn intersect(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        let mut stack = [0usize; MAX_DEPTH + 2];
+        let mut len = if self.nodes.is_empty() { 0 } else { 1 };
+        let dir = Point::new(ray.dir.x, ray.dir.y, ray.dir.z);
+
+        while len > 0 {
+            len -= 1;
+            let i = stack[len];
+            let node = &self.nodes[i];
+
+            match node.bounds.intersects(ray) {
+                Some((enter, exit)) if enter <= t_max && exit >= t_min => (),
+                _ => continue,
+            }
+
+            if node.count > 0 {
+                for item in self.items[node.offset..node.offset + node.count].iter() {
+                    if let Some(hit) = item.intersects(ray, t_min, t_max) {
+                        t_max = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+                continue;
+            }
+
+            // push the far child first so the near one is visited first
+            let (near, far) = if axis(dir, node.axis) < 0.0 { (node.offset, i + 1) } else { (i + 1, node.offset) };
+            stack[len] = far;
+            stack[len + 1] = near;
+            len += 2;
+        }
+
+        closest
+    }
+
+    fn occluded(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        let mut stack = [0usize; MAX_DEPTH + 2];
+        let mut len = if self.nodes.is_empty() { 0 } else { 1 };
+
+        while len > 0 {
+            len -= 1;
+            let i = stack[len];
+            let node = &self.nodes[i];
+
+            match node.bounds.intersects(ray) {
+                Some((enter, exit)) if enter <= t_max && exit >= t_min => (),
+                _ => continue,
+            }
+
+            if node.count > 0 {
+                let items = &self.items[node.offset..node.offset + node.count];
+                if items.iter().any(|item| item.intersects(ray, t_min, t_max).is_some()) {
+                    return true;
+                }
+                continue;
+            }
+
+            stack[len] = i + 1;
+            stack[len + 1] = node.offset;
+            len += 2;
+        }
+
+        false
+    }
+}
+
+impl<T> FromIterator<T> for Bvh<T> where T: Bounded + Viewable {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=T> {
+        Bvh::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bvh::Bvh;
+    use accelerator::Accelerator;
+    use geometry::{Hit,Viewable};
+    use triangle::Triangle;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+    use sampler::Sampler;
+
+    fn random_point(sampler: &mut Sampler) -> Point {
+        Point::new(sampler.next_f64() * 10.0, sampler.next_f64() * 10.0, sampler.next_f64() * 10.0)
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut sampler = Sampler::new(1);
+        let triangles: Vec<Triangle> = (0..200).map(|i| {
+            let a = random_point(&mut sampler);
+            let offset = |s: &mut Sampler| Vector::new(s.next_f64(), s.next_f64(), s.next_f64());
+            Triangle {
+                id: i,
+                ..Triangle::new(a, a.translate(offset(&mut sampler)), a.translate(offset(&mut sampler)))
+            }
+        }).collect();
+        let bvh: Bvh<Triangle> = triangles.iter().cloned().collect();
+
+        for _ in 0..500 {
+            let from = random_point(&mut sampler);
+            let ray = Ray::new(from, random_point(&mut sampler).vector_to(from));
+
+            let expected = triangles.iter().fold(None, |closest: Option<Hit>, t| {
+                let t_max = closest.map_or(f64::INFINITY, |hit| hit.t);
+                t.intersects(ray, 0.0, t_max).or(closest)
+            });
+
+            assert!(bvh.intersect(ray, 0.0, f64::INFINITY).map(|h| h.prim) == expected.map(|h| h.prim));
+            assert!(bvh.occluded(ray, 0.0, f64::INFINITY) == expected.is_some());
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh: Bvh<Triangle> = vec![].into_iter().collect();
+        let ray = Ray::new(Point::zero(), Vector::new(1.0, 0.0, 0.0));
+
+        assert!(bvh.intersect(ray, 0.0, f64::INFINITY).is_none());
+        assert!(!bvh.occluded(ray, 0.0, f64::INFINITY));
+    }
+}
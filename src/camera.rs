@@ -1,87 +1,300 @@
 use point::Point;
 use vector::Vector;
+use transform::Basis;
 use ray::Ray;
+use bounds::Bounds;
+use sampler::Sampler;
+
+pub trait Camera: Send + Sync {
+    /// Returns the ray passing through `(u, v)` on the image plane.
+    /// Both coordinates are measured in image heights from the center of
+    /// the image, `u` increasing to the right and `v` increasing upwards.
+    /// Cameras that need randomness (e.g. to sample a lens) draw it from
+    /// `sampler`.
+    fn ray(&self, u: f64, v: f64, sampler: &mut Sampler) -> Ray;
+
+    /// Moves the camera, without turning it, to the point behind `center`
+    /// along its current viewing direction where a sphere of `radius`
+    /// around `center` just fits in an image `aspect` times as wide as it
+    /// is tall.
+    fn frame(&mut self, center: Point, radius: f64, aspect: f64);
+
+    /// Frames the bounding sphere of `bounds`.
+    fn frame_bounds(&mut self, bounds: Bounds, aspect: f64) {
+        self.frame(bounds.center(), bounds.radius(), aspect);
+    }
+}
+
+impl Basis {
+
+    /// Direction through `(u, v)` on an image plane one unit in front of the
+    /// camera, where the plane is `scale` units tall.
+    fn through(&self, u: f64, v: f64, scale: f64) -> Vector {
+        self.dir + self.right * (u * scale) + self.up * (v * scale)
+    }
+}
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct OrthoCamera {
     loc: Point,
-    dir: Vector,
+    basis: Basis,
+    height: f64,
 }
 
 impl OrthoCamera {
 
-    pub fn new(loc: Point, dir: Vector) -> OrthoCamera {
+    /// Creates a camera at `loc` looking towards `target` that sees a
+    /// region `height` units tall.
+    pub fn new(loc: Point, target: Point, up: Vector, height: f64) -> OrthoCamera {
         OrthoCamera {
-            loc: loc,
-            dir: dir.to_unit(),
+            loc,
+            basis: Basis::look_at(loc, target, up),
+            height,
         }
     }
+}
+
+impl Camera for OrthoCamera {
+    fn ray(&self, u: f64, v: f64, _sampler: &mut Sampler) -> Ray {
+        let offset = self.basis.right * (u * self.height) + self.basis.up * (v * self.height);
+        Ray::new(self.loc.translate(offset), self.basis.dir)
+    }
+
+    fn frame(&mut self, center: Point, radius: f64, aspect: f64) {
+        self.loc = center.translate(self.basis.dir * (-2.0 * radius));
+        // narrow images need to be taller for the sphere to fit across
+        self.height = 2.0 * radius / aspect.min(1.0);
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PerspectiveCamera {
+    loc: Point,
+    basis: Basis,
+    scale: f64,
+}
 
-    pub fn rays(&self, w: u32, h: u32, z: f64) -> Vec<Ray> {
-        let mut rays: Vec<Ray> = vec![];
-        let up = Vector::new(0.0,0.0,1.0);
-        let par = self.dir.cross(up);
-
-        let offset_u = (w as f64 - 1.0) / 2.0;
-        let offset_v = (h as f64 - 1.0) / 2.0;
-
-        for u in 0..w {
-            for v in 0..h {
-                rays.push(Ray::new(
-                    self.loc + Point::new(
-                        /* x */ (u as f64 - offset_u) * z * par.x,
-                        /* y */ (u as f64 - offset_u) * z * par.y,
-                        /* z */ -(v as f64 - offset_v) * z,
-                    ),
-                    self.dir,
-                ));
-            }
+impl PerspectiveCamera {
+
+    /// Creates a pinhole camera at `loc` looking towards `target`, with a
+    /// vertical field of view of `fov` degrees.
+    pub fn new(loc: Point, target: Point, up: Vector, fov: f64) -> PerspectiveCamera {
+        PerspectiveCamera {
+            loc,
+            basis: Basis::look_at(loc, target, up),
+            scale: 2.0 * (fov.to_radians() / 2.0).tan(),
+        }
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn ray(&self, u: f64, v: f64, _sampler: &mut Sampler) -> Ray {
+        Ray::new(self.loc, self.basis.through(u, v, self.scale))
+    }
+
+    fn frame(&mut self, center: Point, radius: f64, aspect: f64) {
+        let distance = framing_distance(radius, self.scale, aspect);
+        self.loc = center.translate(self.basis.dir * -distance);
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ThinLensCamera {
+    loc: Point,
+    basis: Basis,
+    scale: f64,
+    aperture: f64,
+    focus: f64,
+}
+
+impl ThinLensCamera {
+
+    /// Creates a camera like `PerspectiveCamera::new`, but with a lens of
+    /// radius `aperture`. Only points `focus` units in front of the camera
+    /// are perfectly sharp.
+    pub fn new(
+        loc: Point,
+        target: Point,
+        up: Vector,
+        fov: f64,
+        aperture: f64,
+        focus: f64,
+    ) -> ThinLensCamera {
+        ThinLensCamera {
+            loc,
+            basis: Basis::look_at(loc, target, up),
+            scale: 2.0 * (fov.to_radians() / 2.0).tan(),
+            aperture,
+            focus,
         }
+    }
 
-        rays
+    pub fn set_focus(&mut self, focus: f64) {
+        self.focus = focus;
     }
 }
 
+impl Camera for ThinLensCamera {
+    fn ray(&self, u: f64, v: f64, sampler: &mut Sampler) -> Ray {
+        let target = self.loc.translate(self.basis.through(u, v, self.scale) * self.focus);
+
+        let (lu, lv) = sampler.disk();
+        let lens = self.basis.right * (lu * self.aperture) + self.basis.up * (lv * self.aperture);
+        let loc = self.loc.translate(lens);
+
+        Ray::new(loc, target.vector_to(loc))
+    }
+
+    /// Also focuses on `center`.
+    fn frame(&mut self, center: Point, radius: f64, aspect: f64) {
+        let distance = framing_distance(radius, self.scale, aspect);
+        self.loc = center.translate(self.basis.dir * -distance);
+        self.focus = distance;
+    }
+}
+
+/// How far from the center of a sphere of `radius` a camera with an image
+/// plane `scale` units tall and `aspect` times as wide needs to be for the
+/// sphere to fit both vertically and horizontally.
+fn framing_distance(radius: f64, scale: f64, aspect: f64) -> f64 {
+    let tan = scale * aspect.min(1.0) / 2.0;
+    let sin = tan / (1.0 + tan * tan).sqrt();
+    radius / sin
+}
+
 #[cfg(test)]
 mod test {
-    use camera::OrthoCamera;
+    use camera::{Camera,OrthoCamera,PerspectiveCamera,ThinLensCamera};
+    use bounds::Bounds;
     use point::Point;
     use vector::Vector;
     use ray::Ray;
+    use sampler::Sampler;
+
+    fn close(a: Ray, b: Ray) -> bool {
+        a.loc.distance_to(b.loc) < 1e-9 && (a.dir - b.dir).mag() < 1e-9
+    }
 
     #[test]
     fn test_simple_rays() {
         // pointing towards -x
         let camera = OrthoCamera::new(
             Point::new(10.0, 0.0, 0.0),
-            Vector::new(-1.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            2.0,
         );
+        let mut sampler = Sampler::new(0);
 
-        println!("{:?}", camera.rays(2, 2, 1.0));
-        assert!(
-            camera.rays(2, 2, 1.0) ==
-            vec![
-                Ray::new(Point::new(10.0,-0.5,0.5), Vector::new(-1.0, 0.0, 0.0)),
-                Ray::new(Point::new(10.0,-0.5,-0.5), Vector::new(-1.0, 0.0, 0.0)),
-                Ray::new(Point::new(10.0,0.5,0.5), Vector::new(-1.0, 0.0, 0.0)),
-                Ray::new(Point::new(10.0,0.5,-0.5), Vector::new(-1.0, 0.0, 0.0)),
-            ]
-        );
+        assert!(camera.ray(-0.25, 0.25, &mut sampler) == Ray::new(Point::new(10.0,-0.5,0.5), Vector::new(-1.0, 0.0, 0.0)));
+        assert!(camera.ray(-0.25, -0.25, &mut sampler) == Ray::new(Point::new(10.0,-0.5,-0.5), Vector::new(-1.0, 0.0, 0.0)));
+        assert!(camera.ray(0.25, 0.25, &mut sampler) == Ray::new(Point::new(10.0,0.5,0.5), Vector::new(-1.0, 0.0, 0.0)));
+        assert!(camera.ray(0.25, -0.25, &mut sampler) == Ray::new(Point::new(10.0,0.5,-0.5), Vector::new(-1.0, 0.0, 0.0)));
     }
 
     #[test]
     fn test_more_rays() {
         let camera = OrthoCamera::new(
             Point::new(5.0, 5.0, 0.0),
-            Vector::new(-1.0, -1.0, 0.0),
+            Point::new(4.0, 4.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            2.0,
+        );
+        let mut sampler = Sampler::new(0);
+
+        // right is (-1, 1, 0) when looking down (-1, -1, 0)
+        let h = 0.5 / 2.0f64.sqrt();
+        let dir = Vector::new(-1.0, -1.0, 0.0);
+        assert!(close(camera.ray(-0.25, 0.25, &mut sampler), Ray::new(Point::new(5.0 + h, 5.0 - h, 0.5), dir)));
+        assert!(close(camera.ray(0.25, -0.25, &mut sampler), Ray::new(Point::new(5.0 - h, 5.0 + h, -0.5), dir)));
+    }
+
+    #[test]
+    fn test_perspective_rays() {
+        let camera = PerspectiveCamera::new(
+            Point::new(10.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            90.0,
+        );
+        let mut sampler = Sampler::new(0);
+
+        // center of the image looks straight at the target
+        assert!(close(camera.ray(0.0, 0.0, &mut sampler), Ray::new(Point::new(10.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0))));
+
+        // top edge is half the field of view (45 degrees) above center
+        assert!(close(camera.ray(0.0, 0.5, &mut sampler), Ray::new(Point::new(10.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 1.0))));
+        assert!(close(camera.ray(0.5, 0.0, &mut sampler), Ray::new(Point::new(10.0, 0.0, 0.0), Vector::new(-1.0, 1.0, 0.0))));
+    }
+
+    #[test]
+    fn test_thin_lens_rays() {
+        let camera = ThinLensCamera::new(
+            Point::new(10.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            90.0,
+            0.5,
+            5.0,
+        );
+        let mut sampler = Sampler::new(0);
+
+        // every ray through a pixel passes through the same point on the focal plane
+        for _ in 0..10 {
+            let ray = camera.ray(0.5, 0.0, &mut sampler);
+            let t = (ray.loc.x - 5.0) / -ray.dir.x;
+            let p = ray.loc.translate(ray.dir * t);
+
+            assert!(ray.loc.x == 10.0);
+            assert!(p.distance_to(Point::new(5.0, 5.0, 0.0)) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_frame_bounds() {
+        let mut camera = PerspectiveCamera::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            90.0,
         );
+        let mut sampler = Sampler::new(0);
+        camera.frame_bounds(Bounds::new(-1.0, 1.0, -1.0, 1.0, -1.0, 1.0), 1.0);
 
-        println!("{:?}", camera.rays(2, 2, 1.0));
-        assert!(
-            camera.rays(2, 2, 1.0) ==
-            vec![
+        // bounding sphere has radius sqrt(3), so the camera is sqrt(6) away
+        let ray = camera.ray(0.0, 0.0, &mut sampler);
+        assert!(close(ray, Ray::new(Point::new(6.0f64.sqrt(), 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0))));
+    }
 
-            ]
-        )
+    #[test]
+    fn test_frame_bounds_narrow() {
+        let bounds = Bounds::new(-1.0, 1.0, -1.0, 1.0, -1.0, 1.0);
+        let center = Point::new(0.0, 0.0, 0.0);
+        let radius = 3.0f64.sqrt();
+        let mut sampler = Sampler::new(0);
+
+        // an image half as wide as it is tall spans u from -0.25 to 0.25,
+        // so the edge rays should just graze the bounding sphere
+        let mut camera = PerspectiveCamera::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            90.0,
+        );
+        camera.frame_bounds(bounds, 0.5);
+        let edge = camera.ray(0.25, 0.0, &mut sampler);
+        let to_center = center.vector_to(edge.loc);
+        let miss = to_center.cross(edge.dir.to_unit()).mag();
+        assert!((miss - radius).abs() < 1e-9);
+
+        let mut ortho = OrthoCamera::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            1.0,
+        );
+        ortho.frame_bounds(bounds, 0.5);
+        let edge = ortho.ray(0.25, 0.0, &mut sampler);
+        assert!((edge.loc.y.abs() - radius).abs() < 1e-9);
     }
 }
@@ -0,0 +1,307 @@
+use std::fmt;
+use std::str::FromStr;
+
+use raytracer::{AcceleratorKind,BasicMaterial,Color,Light,LoadOptions,Point,Sphere,Transform,Vector,RenderSettings};
+
+pub const USAGE: &str = "\
+Usage: raytracer --input <model.obj> [options]
+
+Options:
+  -i, --input <path>       Wavefront OBJ file to render
+  -o, --output <path>      Where to write the image [default: out.png]
+      --smooth <degrees>   Smooth normals across edges shallower than this angle, 0 to disable [default: 60]
+      --accelerator <kind> bvh or octree [default: bvh]
+      --scale <s|x,y,z>    Scale the model, uniformly or per axis
+      --rotate <x,y,z,deg> Rotate the model around an axis
+      --translate <x,y,z>  Move the model; these three apply in the order given
+      --width <px>         Image width [default: 1000]
+      --height <px>        Image height [default: 1000]
+      --camera <kind>      ortho, perspective or lens [default: perspective]
+      --eye <x,y,z>        Camera position [default: framed around the model]
+      --target <x,y,z>     Point the camera looks at [default: model center]
+      --up <x,y,z>         Camera up vector [default: 0,0,1]
+      --fov <degrees>      Vertical field of view [default: 40]
+      --aperture <r>       Lens radius for the lens camera [default: 0.1]
+      --focus <d>          Focal distance for the lens camera [default: distance to target]
+      --light <x,y,z[,i[,r,g,b]]>
+                           Add a point light with intensity i and color r,g,b,
+                           may be repeated [default: one light above the model]
+      --sphere <x,y,z,r[,r,g,b]>
+                           Add a sphere with the given color next to the model,
+                           may be repeated
+      --ground             Put an infinite floor under the scene
+      --background <r,g,b> Color where rays miss the model [default: 0,0,0]
+      --mirror <x,y,z,r>   Add a mirror ball, may be repeated
+      --glass <x,y,z,r[,ior]>
+                           Add a glass ball [default ior: 1.5], may be repeated
+      --max-depth <n>      Times rays may bounce off mirrors and glass [default: 5]
+      --samples <n>        Rays per pixel [default: 1]
+      --threads <n>        Worker threads [default: number of cpus]
+  -h, --help               Print this message";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum CameraKind {
+    Ortho,
+    Perspective,
+    Lens,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Options {
+    pub input: String,
+    pub output: String,
+    pub load: LoadOptions,
+    pub settings: RenderSettings,
+    pub camera: CameraKind,
+    pub eye: Option<Point>,
+    pub target: Option<Point>,
+    pub up: Vector,
+    pub fov: f64,
+    pub aperture: f64,
+    pub focus: Option<f64>,
+    pub lights: Vec<Light>,
+    pub spheres: Vec<(Sphere, BasicMaterial)>,
+    pub ground: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CliError {
+    Help,
+    MissingInput,
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownFlag(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Help => write!(f, "help requested"),
+            CliError::MissingInput => write!(f, "no input model given, use --input <path>"),
+            CliError::MissingValue(ref flag) => write!(f, "{} expects a value", flag),
+            CliError::InvalidValue(ref flag, ref value) => write!(f, "invalid value '{}' for {}", value, flag),
+            CliError::UnknownFlag(ref flag) => write!(f, "unknown option '{}'", flag),
+        }
+    }
+}
+
+pub fn parse<I>(args: I) -> Result<Options, CliError> where I: IntoIterator<Item=String> {
+    let mut args = args.into_iter();
+    let mut input = None;
+    let mut options = Options {
+        input: String::new(),
+        output: "out.png".to_string(),
+        load: LoadOptions::default(),
+        settings: RenderSettings::default(),
+        camera: CameraKind::Perspective,
+        eye: None,
+        target: None,
+        up: Vector::new(0.0, 0.0, 1.0),
+        fov: 40.0,
+        aperture: 0.1,
+        focus: None,
+        lights: vec![],
+        spheres: vec![],
+        ground: false,
+    };
+
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(CliError::Help);
+        }
+
+        if flag == "--ground" {
+            options.ground = true;
+            continue;
+        }
+
+        let value = match flag.as_ref() {
+            "-i" | "--input" | "-o" | "--output" | "--width" | "--height" | "--camera" |
+            "--eye" | "--target" | "--up" | "--fov" | "--aperture" | "--focus" | "--light" |
+            "--sphere" | "--mirror" | "--glass" | "--max-depth" | "--samples" | "--threads" | "--smooth" | "--background" | "--accelerator" |
+            "--scale" | "--rotate" | "--translate" => match args.next() {
+                Some(value) => value,
+                None => return Err(CliError::MissingValue(flag)),
+            },
+            _ => return Err(CliError::UnknownFlag(flag)),
+        };
+
+        match flag.as_ref() {
+            "-i" | "--input" => input = Some(value),
+            "-o" | "--output" => options.output = value,
+            "--width" => options.settings.width = parse_count(&flag, &value)?,
+            "--height" => options.settings.height = parse_count(&flag, &value)?,
+            "--samples" => options.settings.samples = parse_count(&flag, &value)?,
+            "--threads" => options.settings.threads = parse_count(&flag, &value)?,
+            "--smooth" => {
+                options.load.smooth_angle = match parse_number(&flag, &value)? {
+                    0.0 => None,
+                    angle => Some(angle),
+                }
+            },
+            "--background" => {
+                let c = parse_point(&flag, &value)?;
+                options.settings.background = Color::new(c.x, c.y, c.z);
+            },
+            "--fov" => options.fov = parse_number(&flag, &value)?,
+            "--aperture" => options.aperture = parse_number(&flag, &value)?,
+            "--focus" => options.focus = Some(parse_number(&flag, &value)?),
+            "--eye" => options.eye = Some(parse_point(&flag, &value)?),
+            "--target" => options.target = Some(parse_point(&flag, &value)?),
+            "--light" => options.lights.push(parse_light(&flag, &value)?),
+            "--sphere" | "--mirror" | "--glass" => options.spheres.push(parse_sphere(&flag, &value)?),
+            "--max-depth" => options.settings.max_depth = parse_number(&flag, &value)?,
+            "--up" => {
+                let p = parse_point(&flag, &value)?;
+                options.up = Vector::new(p.x, p.y, p.z);
+            },
+            "--scale" | "--rotate" | "--translate" => {
+                let t = parse_transform(&flag, &value)?;
+                options.load.transform = options.load.transform.then(&t);
+            },
+            "--accelerator" => {
+                options.load.accelerator = match value.as_ref() {
+                    "bvh" => AcceleratorKind::Bvh,
+                    "octree" => AcceleratorKind::Octree,
+                    _ => return Err(CliError::InvalidValue(flag, value)),
+                }
+            },
+            "--camera" => {
+                options.camera = match value.as_ref() {
+                    "ortho" => CameraKind::Ortho,
+                    "perspective" => CameraKind::Perspective,
+                    "lens" => CameraKind::Lens,
+                    _ => return Err(CliError::InvalidValue(flag, value)),
+                }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    options.input = input.ok_or(CliError::MissingInput)?;
+    Ok(options)
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    T::from_str(value).map_err(|_| CliError::InvalidValue(flag.to_string(), value.to_string()))
+}
+
+/// A number of pixels, samples or threads, which can't be zero.
+fn parse_count(flag: &str, value: &str) -> Result<u32, CliError> {
+    match parse_number(flag, value)? {
+        0 => Err(CliError::InvalidValue(flag.to_string(), value.to_string())),
+        n => Ok(n),
+    }
+}
+
+fn parse_list(flag: &str, value: &str) -> Result<Vec<f64>, CliError> {
+    value
+        .split(',')
+        .map(|n| parse_number(flag, n.trim()))
+        .collect()
+}
+
+fn parse_point(flag: &str, value: &str) -> Result<Point, CliError> {
+    let coords = parse_list(flag, value)?;
+
+    match coords.len() {
+        3 => Ok(Point::new(coords[0], coords[1], coords[2])),
+        _ => Err(CliError::InvalidValue(flag.to_string(), value.to_string())),
+    }
+}
+
+fn parse_light(flag: &str, value: &str) -> Result<Light, CliError> {
+    let n = parse_list(flag, value)?;
+
+    match n.len() {
+        3 => Ok(Light::new(Point::new(n[0], n[1], n[2]))),
+        4 => Ok(Light::with_color(Point::new(n[0], n[1], n[2]), n[3], Color::white())),
+        7 => Ok(Light::with_color(Point::new(n[0], n[1], n[2]), n[3], Color::new(n[4], n[5], n[6]))),
+        _ => Err(CliError::InvalidValue(flag.to_string(), value.to_string())),
+    }
+}
+
+fn parse_transform(flag: &str, value: &str) -> Result<Transform, CliError> {
+    let n = parse_list(flag, value)?;
+
+    match (flag, n.len()) {
+        ("--scale", 1) if n[0] != 0.0 => Ok(Transform::scale(n[0], n[0], n[0])),
+        ("--scale", 3) if !n.contains(&0.0) => Ok(Transform::scale(n[0], n[1], n[2])),
+        ("--rotate", 4) => Ok(Transform::rotate(Vector::new(n[0], n[1], n[2]), n[3])),
+        ("--translate", 3) => Ok(Transform::translate(Vector::new(n[0], n[1], n[2]))),
+        _ => Err(CliError::InvalidValue(flag.to_string(), value.to_string())),
+    }
+}
+
+fn parse_sphere(flag: &str, value: &str) -> Result<(Sphere, BasicMaterial), CliError> {
+    let n = parse_list(flag, value)?;
+    if n.len() < 4 || n[3] <= 0.0 {
+        return Err(CliError::InvalidValue(flag.to_string(), value.to_string()));
+    }
+
+    let sphere = Sphere::new(Point::new(n[0], n[1], n[2]), n[3]);
+    let material = match (flag, n.len()) {
+        ("--sphere", 4) => BasicMaterial::default(),
+        ("--sphere", 7) => BasicMaterial::diffuse(Color::new(n[4], n[5], n[6])),
+        ("--mirror", 4) => BasicMaterial::mirror(Color::white()),
+        ("--glass", 4) => BasicMaterial::glass(1.5),
+        ("--glass", 5) => BasicMaterial::glass(n[4]),
+        _ => return Err(CliError::InvalidValue(flag.to_string(), value.to_string())),
+    };
+
+    Ok((sphere, material))
+}
+
+#[cfg(test)]
+mod test {
+    use cli::{parse,CameraKind,CliError};
+    use raytracer::{BasicMaterial,Color,Light,Point,Sphere,Transform,Vector};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let options = parse(args(
+            "-i monkey.obj --width 640 --height 360 --camera lens --eye 1,2,3 --light 0,0,10 --light 5,5,5,0.5,1,0,0 --sphere 0,0,1,0.5 --sphere 1,1,1,1,1,0,0 --glass 0,0,0,1,1.3 --max-depth 3 --ground --scale 2 --translate 0,0,1"
+        )).unwrap();
+
+        assert!(options.input == "monkey.obj");
+        assert!(options.output == "out.png");
+        assert!(options.settings.width == 640 && options.settings.height == 360);
+        assert!(options.camera == CameraKind::Lens);
+        assert!(options.load.smooth_angle == Some(60.0));
+        assert!(options.eye == Some(Point::new(1.0, 2.0, 3.0)));
+        assert!(options.lights == vec![
+            Light::new(Point::new(0.0, 0.0, 10.0)),
+            Light::with_color(Point::new(5.0, 5.0, 5.0), 0.5, Color::new(1.0, 0.0, 0.0)),
+        ]);
+        assert!(options.ground);
+        assert!(options.load.transform == Transform::scale(2.0, 2.0, 2.0).then(&Transform::translate(Vector::new(0.0, 0.0, 1.0))));
+        assert!(options.spheres == vec![
+            (Sphere::new(Point::new(0.0, 0.0, 1.0), 0.5), BasicMaterial::default()),
+            (Sphere::new(Point::new(1.0, 1.0, 1.0), 1.0), BasicMaterial::diffuse(Color::new(1.0, 0.0, 0.0))),
+            (Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0), BasicMaterial::glass(1.3)),
+        ]);
+        assert!(options.settings.max_depth == 3);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(args("")) == Err(CliError::MissingInput));
+        assert!(parse(args("-i a.obj --width")) == Err(CliError::MissingValue("--width".to_string())));
+        assert!(parse(args("-i a.obj --width wide")) == Err(CliError::InvalidValue("--width".to_string(), "wide".to_string())));
+        assert!(parse(args("-i a.obj --width 0")) == Err(CliError::InvalidValue("--width".to_string(), "0".to_string())));
+        assert!(parse(args("-i a.obj --height 0")) == Err(CliError::InvalidValue("--height".to_string(), "0".to_string())));
+        assert!(parse(args("-i a.obj --samples 0")) == Err(CliError::InvalidValue("--samples".to_string(), "0".to_string())));
+        assert!(parse(args("-i a.obj --threads 0")) == Err(CliError::InvalidValue("--threads".to_string(), "0".to_string())));
+        assert!(parse(args("-i a.obj --eye 1,2")) == Err(CliError::InvalidValue("--eye".to_string(), "1,2".to_string())));
+        assert!(parse(args("-i a.obj --light 1,2,3,4,5")) == Err(CliError::InvalidValue("--light".to_string(), "1,2,3,4,5".to_string())));
+        assert!(parse(args("-i a.obj --mirror 0,0,0,1,2")) == Err(CliError::InvalidValue("--mirror".to_string(), "0,0,0,1,2".to_string())));
+        assert!(parse(args("-i a.obj --sphere 0,0,0,-1")) == Err(CliError::InvalidValue("--sphere".to_string(), "0,0,0,-1".to_string())));
+        assert!(parse(args("-i a.obj --scale 0")) == Err(CliError::InvalidValue("--scale".to_string(), "0".to_string())));
+        assert!(parse(args("-i a.obj --fisheye")) == Err(CliError::UnknownFlag("--fisheye".to_string())));
+        assert!(parse(args("-i a.obj --help")) == Err(CliError::Help));
+    }
+}
@@ -0,0 +1,98 @@
+use std::ops::{Add,Mul};
+
+/// Linear RGB color, components nominally between 0 and 1.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Color {
+
+    pub fn new(r: f64, g: f64, b: f64) -> Color {
+        Color {
+            r,
+            g,
+            b,
+        }
+    }
+
+    pub fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    /// Clamps to `[0, 1]` and encodes with the sRGB transfer curve.
+    pub fn to_srgb8(self) -> [u8; 3] {
+        fn encode(c: f64) -> u8 {
+            let c = c.clamp(0.0, 1.0);
+            let s = if c <= 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+            (s * 255.0).round() as u8
+        }
+
+        [encode(self.r), encode(self.g), encode(self.b)]
+    }
+
+    /// Decodes 8-bit sRGB, the inverse of `to_srgb8`.
+    pub fn from_srgb8(rgb: [u8; 3]) -> Color {
+        fn decode(s: u8) -> f64 {
+            let s = s as f64 / 255.0;
+            if s <= 0.04045 { s / 12.92 } else { ((s + 0.055) / 1.055).powf(2.4) }
+        }
+
+        Color::new(decode(rgb[0]), decode(rgb[1]), decode(rgb[2]))
+    }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, c: Color) -> Color {
+        Color::new(self.r + c.r, self.g + c.g, self.b + c.b)
+    }
+}
+
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, c: Color) -> Color {
+        Color::new(self.r * c.r, self.g * c.g, self.b * c.b)
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, n: f64) -> Color {
+        Color::new(self.r * n, self.g * n, self.b * n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use color::Color;
+
+    #[test]
+    fn test_ops() {
+        assert!(Color::new(0.1, 0.2, 0.3) + Color::new(0.1, 0.2, 0.3) == Color::new(0.2, 0.4, 0.6));
+        assert!(Color::new(0.5, 1.0, 0.0) * Color::new(0.5, 0.5, 0.5) == Color::new(0.25, 0.5, 0.0));
+        assert!(Color::white() * 0.5 == Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_srgb() {
+        assert!(Color::black().to_srgb8() == [0, 0, 0]);
+        assert!(Color::white().to_srgb8() == [255, 255, 255]);
+        assert!(Color::new(0.5, -1.0, 0.001).to_srgb8() == [188, 0, 3]);
+        assert!(Color::from_srgb8([0, 255, 0]) == Color::new(0.0, 1.0, 0.0));
+
+        for s in 0..256 {
+            let s = s as u8;
+            assert!(Color::from_srgb8([s, s, s]).to_srgb8() == [s, s, s]);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use image::{Rgb,RgbImage,Rgba,RgbaImage};
+
+use color::Color;
+
+/// Linear color of every pixel of a rendered image, kept in floating point
+/// until it is converted for saving.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Film {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Film {
+
+    pub fn new(width: u32, height: u32) -> Film {
+        Film {
+            width,
+            height,
+            pixels: vec![Color::black(); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[y as usize * self.width as usize + x as usize] = color;
+    }
+
+    /// 8-bit sRGB image.
+    pub fn to_rgb8(&self) -> RgbImage {
+        RgbImage::from_fn(self.width, self.height, |x, y| Rgb(self.get(x, y).to_srgb8()))
+    }
+
+    /// 8-bit sRGB image with an opaque alpha channel.
+    pub fn to_rgba8(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            let [r, g, b] = self.get(x, y).to_srgb8();
+            Rgba([r, g, b, 255])
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use film::Film;
+    use color::Color;
+
+    #[test]
+    fn test_convert() {
+        let mut film = Film::new(2, 1);
+        film.set(1, 0, Color::new(1.0, 0.5, 2.0));
+
+        let rgb = film.to_rgb8();
+        assert!(rgb.get_pixel(0, 0).data == [0, 0, 0]);
+        assert!(rgb.get_pixel(1, 0).data == [255, 188, 255]);
+
+        let rgba = film.to_rgba8();
+        assert!(rgba.get_pixel(1, 0).data == [255, 188, 255, 255]);
+    }
+}
@@ -1,11 +1,30 @@
 use bounds::Bounds;
 use ray::Ray;
 use point::Point;
+use vector::Vector;
+
+/// Where a ray hit a primitive, and what it hit.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Hit {
+    /// Distance along the ray.
+    pub t: f64,
+    pub point: Point,
+    /// True normal of the surface.
+    pub normal: Vector,
+    /// Normal to shade with, e.g. interpolated from vertex normals.
+    pub shading: Vector,
+    pub uv: (f64, f64),
+    /// Id of the primitive that was hit.
+    pub prim: usize,
+    pub material: Option<usize>,
+}
 
 pub trait Bounded {
     fn bounds(&self) -> Bounds;
 }
 
 pub trait Viewable {
-    fn intersects(&self, r: Ray) -> Option<Point>;
+    /// Finds where `r` hits, ignoring hits closer than `t_min` or further
+    /// than `t_max` along the ray.
+    fn intersects(&self, r: Ray, t_min: f64, t_max: f64) -> Option<Hit>;
 }
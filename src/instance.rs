@@ -0,0 +1,135 @@
+use std::fmt;
+use std::sync::Arc;
+
+use geometry::{Bounded,Hit,Viewable};
+use accelerator::Accelerator;
+use transform::Transform;
+use point::Point;
+use bounds::Bounds;
+use ray::Ray;
+
+/// A copy of a shared acceleration structure, placed by its own transform.
+/// Any number of instances can share one `object` without copying it.
+#[derive(Clone)]
+pub struct Instance {
+    pub object: Arc<dyn Accelerator>,
+    pub transform: Transform,
+    pub id: usize,
+    /// Overrides the materials of the object when set.
+    pub material: Option<usize>,
+}
+
+impl Instance {
+
+    pub fn new(object: Arc<dyn Accelerator>, transform: Transform) -> Instance {
+        Instance {
+            object,
+            transform,
+            id: 0,
+            material: None,
+        }
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("bounds", &self.object.bounds())
+            .field("transform", &self.transform)
+            .field("id", &self.id)
+            .field("material", &self.material)
+            .finish()
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Instance) -> bool {
+        Arc::ptr_eq(&self.object, &other.object)
+            && self.transform == other.transform
+            && self.id == other.id
+            && self.material == other.material
+    }
+}
+
+impl Bounded for Instance {
+    fn bounds(&self) -> Bounds {
+        let b = self.object.bounds();
+        let corners: Vec<Point> = (0..8).map(|i| self.transform.point(Point::new(
+            if i & 1 == 0 { b.xmin } else { b.xmax },
+            if i & 2 == 0 { b.ymin } else { b.ymax },
+            if i & 4 == 0 { b.zmin } else { b.zmax },
+        ))).collect();
+
+        let first = corners[0];
+        corners.iter().fold(Bounds::new(first.x, first.x, first.y, first.y, first.z, first.z), |sum, c| {
+            sum + Bounds::new(c.x, c.x, c.y, c.y, c.z, c.z)
+        })
+    }
+}
+
+impl Viewable for Instance {
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        // trace in object space, where distances are stretched by however
+        // much the inverse transform stretches the ray's direction
+        let inverse = self.transform.inverse();
+        let dir = inverse.vector(ray.dir);
+        let stretch = dir.mag();
+        let local = Ray::new(inverse.point(ray.loc), dir);
+
+        let hit = self.object.intersect(local, t_min * stretch, t_max * stretch)?;
+
+        Some(Hit {
+            t: hit.t / stretch,
+            point: self.transform.point(hit.point),
+            normal: self.transform.normal(hit.normal),
+            shading: self.transform.normal(hit.shading),
+            uv: hit.uv,
+            prim: self.id,
+            material: self.material.or(hit.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use instance::Instance;
+    use accelerator;
+    use accelerator::{Accelerator,AcceleratorKind};
+    use geometry::{Bounded,Viewable};
+    use triangle::Triangle;
+    use transform::Transform;
+    use bounds::Bounds;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_intersects() {
+        let object: Arc<dyn Accelerator> = Arc::from(accelerator::build(AcceleratorKind::Bvh, vec![
+            Triangle::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0), Point::new(0.0, 1.0, 0.0)),
+        ]));
+        let transform = Transform::scale(2.0, 2.0, 2.0)
+            .then(&Transform::rotate(Vector::new(1.0, 0.0, 0.0), 90.0))
+            .then(&Transform::translate(Vector::new(0.0, 5.0, 0.0)));
+        let instance = Instance::new(object.clone(), transform);
+
+        // the triangle now stands upright in the plane y = 5, facing -y
+        let b = instance.bounds();
+        assert!((b.ymin - 5.0).abs() < 1e-9 && (b.ymax - 5.0).abs() < 1e-9);
+        assert!((b.xmax - 2.0).abs() < 1e-9 && (b.zmax - 2.0).abs() < 1e-9);
+
+        let ray = Ray::new(Point::new(0.5, 0.0, 0.5), Vector::new(0.0, 1.0, 0.0));
+        let hit = instance.intersects(ray, 0.0, 100.0).unwrap();
+        assert!((hit.t - 5.0).abs() < 1e-9);
+        assert!(hit.point.distance_to(Point::new(0.5, 5.0, 0.5)) < 1e-9);
+        assert!((hit.normal.y + 1.0).abs() < 1e-9);
+
+        assert!(instance.intersects(ray, 0.0, 4.0).is_none());
+        assert!(instance.intersects(Ray::new(Point::new(1.5, 0.0, 1.5), Vector::new(0.0, 1.0, 0.0)), 0.0, 100.0).is_none());
+
+        // the object itself is untouched
+        assert!(object.bounds() == Bounds::new(0.0, 1.0, 0.0, 1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,51 @@
+//! Photos of monkeys. Loads Wavefront OBJ models into a `Scene`, points a
+//! `Camera` at them and `render`s them into an image buffer.
+
+extern crate image;
+extern crate num_cpus;
+
+pub mod point;
+pub mod vector;
+pub mod matrix;
+pub mod transform;
+pub mod bounds;
+pub mod geometry;
+pub mod triangle;
+pub mod mesh;
+pub mod sphere;
+pub mod plane;
+pub mod shape;
+pub mod instance;
+pub mod ray;
+pub mod octree;
+pub mod bvh;
+pub mod accelerator;
+pub mod camera;
+pub mod scene;
+pub mod render;
+pub mod sampler;
+pub mod obj;
+pub mod mtl;
+pub mod color;
+pub mod light;
+pub mod material;
+pub mod texture;
+pub mod film;
+
+pub use point::Point;
+pub use vector::Vector;
+pub use transform::Transform;
+pub use color::Color;
+pub use light::Light;
+pub use material::{BasicMaterial,Material,TexturedMaterial};
+pub use texture::Texture;
+pub use sphere::Sphere;
+pub use plane::Plane;
+pub use shape::Shape;
+pub use mesh::{Mesh,MeshTriangle};
+pub use instance::Instance;
+pub use scene::{LoadOptions,Scene};
+pub use camera::{Camera,OrthoCamera,PerspectiveCamera,ThinLensCamera};
+pub use render::{render,RenderError,RenderSettings};
+pub use film::Film;
+pub use accelerator::{Accelerator,AcceleratorKind};
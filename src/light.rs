@@ -0,0 +1,28 @@
+use point::Point;
+use color::Color;
+
+/// Light shining equally in all directions from a single point. It isn't
+/// attenuated with distance, so an `intensity` of 1 lights a surface
+/// facing it fully no matter how far away it is.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Light {
+    pub loc: Point,
+    pub intensity: f64,
+    pub color: Color,
+}
+
+impl Light {
+
+    /// White light with an intensity of 1.
+    pub fn new(loc: Point) -> Light {
+        Light::with_color(loc, 1.0, Color::white())
+    }
+
+    pub fn with_color(loc: Point, intensity: f64, color: Color) -> Light {
+        Light {
+            loc,
+            intensity,
+            color,
+        }
+    }
+}
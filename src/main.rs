@@ -1,40 +1,124 @@
-#[macro_use]
-extern crate image;
-extern crate num_cpus;
-
-use std::fs::File;
-
-use scene::Scene;
-use camera::OrthoCamera;
-use point::Point;
-use vector::Vector;
-use render::render;
-
-mod point;
-mod vector;
-mod bounds;
-mod geometry;
-mod triangle;
-mod ray;
-mod octree;
-mod camera;
-mod scene;
-mod render;
+extern crate raytracer;
+
+use std::env;
+use std::process;
+
+use raytracer::{render,Light,Scene,Sphere,Vector};
+use raytracer::{Camera,OrthoCamera,PerspectiveCamera,ThinLensCamera};
+use cli::{CameraKind,CliError,Options};
+
+mod cli;
 
 fn main() {
-    let src = "/Users/nickclaw/workspace/rust/raytracer/data/verts.obj";
-    let dest = "/Users/nickclaw/workspace/rust/raytracer/out.png";
-    let input = File::open(src).unwrap();
-    let mut scene = Scene::from_file(input);
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(CliError::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            process::exit(2);
+        },
+    };
+
+    if let Err(err) = run(options) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut scene = Scene::open_with(&options.input, &options.load)
+        .map_err(|e| format!("could not load {}: {}", options.input, e))?;
+
+    for warning in scene.warnings.iter() {
+        eprintln!("warning: {}", warning);
+    }
+
+    for &(sphere, material) in options.spheres.iter() {
+        let material = scene.add_material(material);
+        scene.add_sphere(Sphere { material: Some(material), ..sphere });
+    }
+
+    if options.ground {
+        scene.add_ground_plane();
+    }
+
+    set_camera(&mut scene, &options);
+
+    for light in options.lights.iter() {
+        scene.add_light(*light);
+    }
+
+    if options.lights.is_empty() {
+        let bounds = scene.bounds();
+        let offset = Vector::new(1.0, 1.0, 2.0) * (2.0 * bounds.radius());
+        scene.add_light(Light::new(bounds.center().translate(offset)));
+    }
+
+    let film = render(scene, &options.settings).map_err(|e| e.to_string())?;
+    film.to_rgb8().save(&options.output)
+        .map_err(|e| format!("could not write {}: {}", options.output, e))
+}
+
+/// Places the requested camera. Unless an eye position was given it is
+/// moved back until the whole model is in view, looking at the model's
+/// center or at the requested target.
+fn set_camera(scene: &mut Scene, options: &Options) {
+    let bounds = scene.bounds();
+    let target = options.target.unwrap_or_else(|| bounds.center());
+    let eye = options.eye.unwrap_or_else(|| target.translate(Vector::new(1.0, 1.0, 0.0)));
+    let focus = options.focus.unwrap_or_else(|| eye.distance_to(target));
+    // big enough around the target to hold the model's bounding sphere
+    let radius = bounds.radius() + target.distance_to(bounds.center());
+    let frame = options.eye.is_none();
+    let aspect = options.settings.width as f64 / options.settings.height as f64;
+
+    match options.camera {
+        CameraKind::Ortho => {
+            let mut camera = OrthoCamera::new(eye, target, options.up, 2.0 * bounds.radius());
+            if frame { camera.frame(target, radius, aspect); }
+            scene.set_camera(camera);
+        },
+        CameraKind::Perspective => {
+            let mut camera = PerspectiveCamera::new(eye, target, options.up, options.fov);
+            if frame { camera.frame(target, radius, aspect); }
+            scene.set_camera(camera);
+        },
+        CameraKind::Lens => {
+            let mut camera = ThinLensCamera::new(eye, target, options.up, options.fov, options.aperture, focus);
+            if frame { camera.frame(target, radius, aspect); }
+            if let Some(focus) = options.focus { camera.set_focus(focus); }
+            scene.set_camera(camera);
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use set_camera;
+    use cli::parse;
+    use raytracer::{AcceleratorKind,Point,Scene,Sphere};
+    use raytracer::sampler::Sampler;
+
+    #[test]
+    fn test_target_without_eye() {
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        scene.add_sphere(Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0));
+        let target = Point::new(0.0, 0.0, 1.0);
 
-    scene.set_camera(OrthoCamera::new(
-        Point::new(10.0, 10.0, 0.0),
-        Vector::new(-1.0, -1.0, 0.0),
-    ));
+        let options = parse(vec!["-i", "a.obj", "--target", "0,0,1"].into_iter().map(String::from)).unwrap();
+        set_camera(&mut scene, &options);
 
-    scene.add_light(Point::new(0.0, 0.0, 10.0));
-    scene.add_light(Point::new(10.0, 10.0, 10.0));
+        // the center of the image looks at the target, not the sphere's center
+        let mut sampler = Sampler::new(0);
+        let camera = scene.camera.as_ref().unwrap();
+        let ray = camera.ray(0.0, 0.0, &mut sampler);
+        let to_target = target.vector_to(ray.loc);
+        assert!(to_target.cross(ray.dir).mag() < 1e-9 && to_target.dot(ray.dir) > 0.0);
 
-    let image = render(scene);
-    image.as_luma8().unwrap().save(dest).unwrap();
+        // and it is far enough back to see the whole sphere
+        assert!(ray.loc.distance_to(target) > 2.0);
+    }
 }
@@ -0,0 +1,202 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use color::Color;
+use texture::Texture;
+
+/// How a surface scatters and emits light.
+pub trait Material: Debug + Send + Sync {
+    /// Diffuse reflectance at texture coordinates `uv`.
+    fn albedo(&self, uv: (f64, f64)) -> Color;
+
+    /// Color of mirror-like highlights and reflections.
+    fn specular(&self) -> Color {
+        Color::black()
+    }
+
+    /// How blurry highlights are, from 0 for a perfect mirror to 1.
+    fn roughness(&self) -> f64 {
+        1.0
+    }
+
+    /// Blinn-Phong exponent for highlights, higher for smaller and sharper
+    /// ones. Derived from `roughness` unless overridden.
+    fn shininess(&self) -> f64 {
+        let r = self.roughness().max(1e-3);
+        (2.0 / (r * r) - 2.0).max(0.0)
+    }
+
+    /// Light given off by the surface itself.
+    fn emission(&self) -> Color {
+        Color::black()
+    }
+
+    /// Color of perfect mirror reflections of the rest of the scene.
+    fn reflection(&self) -> Color {
+        Color::black()
+    }
+
+    /// Fraction of light let through the surface rather than reflected.
+    fn transmission(&self) -> f64 {
+        0.0
+    }
+
+    /// Index of refraction of what's inside the surface.
+    fn ior(&self) -> f64 {
+        1.0
+    }
+}
+
+/// A material with the same properties all over.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BasicMaterial {
+    pub albedo: Color,
+    pub specular: Color,
+    pub roughness: f64,
+    pub emission: Color,
+    pub reflection: Color,
+    pub transmission: f64,
+    pub ior: f64,
+}
+
+impl BasicMaterial {
+
+    /// A diffuse material with white highlights of the given shininess.
+    pub fn glossy(albedo: Color, specular: f64, shininess: f64) -> BasicMaterial {
+        BasicMaterial {
+            specular: Color::white() * specular,
+            roughness: (2.0 / (shininess.max(0.0) + 2.0)).sqrt(),
+            ..BasicMaterial::diffuse(albedo)
+        }
+    }
+
+    /// A purely diffuse material.
+    pub fn diffuse(albedo: Color) -> BasicMaterial {
+        BasicMaterial {
+            albedo,
+            specular: Color::black(),
+            roughness: 1.0,
+            emission: Color::black(),
+            reflection: Color::black(),
+            transmission: 0.0,
+            ior: 1.0,
+        }
+    }
+
+    /// A perfect mirror tinted by `color`.
+    pub fn mirror(color: Color) -> BasicMaterial {
+        BasicMaterial {
+            reflection: color,
+            ..BasicMaterial::diffuse(Color::black())
+        }
+    }
+
+    /// Clear glass with the given index of refraction, with small sharp
+    /// highlights.
+    pub fn glass(ior: f64) -> BasicMaterial {
+        BasicMaterial {
+            transmission: 1.0,
+            ior,
+            ..BasicMaterial::glossy(Color::white(), 1.0, 1000.0)
+        }
+    }
+}
+
+/// White and diffuse, what surfaces without a material are shaded with.
+impl Default for BasicMaterial {
+    fn default() -> BasicMaterial {
+        BasicMaterial::diffuse(Color::white())
+    }
+}
+
+impl Material for BasicMaterial {
+    fn albedo(&self, _uv: (f64, f64)) -> Color {
+        self.albedo
+    }
+
+    fn specular(&self) -> Color {
+        self.specular
+    }
+
+    fn roughness(&self) -> f64 {
+        self.roughness
+    }
+
+    fn emission(&self) -> Color {
+        self.emission
+    }
+
+    fn reflection(&self) -> Color {
+        self.reflection
+    }
+
+    fn transmission(&self) -> f64 {
+        self.transmission
+    }
+
+    fn ior(&self) -> f64 {
+        self.ior
+    }
+}
+
+/// A `BasicMaterial` whose albedo is multiplied by a texture.
+#[derive(Debug, Clone)]
+pub struct TexturedMaterial {
+    pub base: BasicMaterial,
+    pub texture: Arc<Texture>,
+}
+
+impl TexturedMaterial {
+
+    pub fn new(base: BasicMaterial, texture: Arc<Texture>) -> TexturedMaterial {
+        TexturedMaterial {
+            base,
+            texture,
+        }
+    }
+}
+
+impl Material for TexturedMaterial {
+    fn albedo(&self, uv: (f64, f64)) -> Color {
+        self.base.albedo * self.texture.sample(uv)
+    }
+
+    fn specular(&self) -> Color {
+        self.base.specular
+    }
+
+    fn roughness(&self) -> f64 {
+        self.base.roughness
+    }
+
+    fn emission(&self) -> Color {
+        self.base.emission
+    }
+
+    fn reflection(&self) -> Color {
+        self.base.reflection
+    }
+
+    fn transmission(&self) -> f64 {
+        self.base.transmission
+    }
+
+    fn ior(&self) -> f64 {
+        self.base.ior
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use material::{BasicMaterial,Material};
+    use color::Color;
+
+    #[test]
+    fn test_shininess() {
+        let glossy = BasicMaterial::glossy(Color::white(), 0.5, 98.0);
+        assert!(glossy.specular == Color::new(0.5, 0.5, 0.5));
+        assert!((glossy.shininess() - 98.0).abs() < 1e-9);
+
+        assert!(BasicMaterial::default().shininess() == 0.0);
+    }
+}
@@ -0,0 +1,81 @@
+use std::ops::Mul;
+
+/// A 4x4 matrix in row-major order, acting on column vectors.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 {
+            m,
+        }
+    }
+
+    pub fn identity() -> Matrix4 {
+        Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.m[j][i];
+            }
+        }
+
+        Matrix4::new(m)
+    }
+
+    /// Determinant of the upper left 3x3 block, negative when the matrix
+    /// mirrors space.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, o: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| self.m[i][k] * o.m[k][j]).sum();
+            }
+        }
+
+        Matrix4::new(m)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use matrix::Matrix4;
+
+    #[test]
+    fn test_mul() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 3.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        assert!(a * Matrix4::identity() == a);
+        assert!(Matrix4::identity() * a == a);
+        assert!((a * a).m[0] == [1.0, 4.0, 0.0, 6.0]);
+        assert!(a.transpose().m[3] == [0.0, 3.0, 0.0, 1.0]);
+        assert!(a.determinant3() == 2.0);
+    }
+}
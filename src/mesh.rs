@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use geometry::{Bounded,Hit,Viewable};
+use triangle;
+use obj::Obj;
+use transform::Transform;
+use point::Point;
+use vector::Vector;
+use bounds::Bounds;
+use ray::Ray;
+
+/// One triangle of a `Mesh`, as indices into its buffers.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct MeshFace {
+    pub positions: [u32; 3],
+    pub normals: Option<[u32; 3]>,
+    pub uvs: Option<[u32; 3]>,
+    pub material: Option<usize>,
+}
+
+/// Triangles sharing vertex, normal and texture coordinate buffers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mesh {
+    pub positions: Vec<Point>,
+    /// Unit vertex normals.
+    pub normals: Vec<Vector>,
+    pub uvs: Vec<(f64, f64)>,
+    pub faces: Vec<MeshFace>,
+}
+
+impl Mesh {
+
+    /// Copies the faces of `obj` placed by `transform`, using its vertex
+    /// normals and texture coordinates where every corner of a face has
+    /// them.
+    pub fn from_obj(obj: &Obj, transform: &Transform) -> Mesh {
+        let mirror = transform.swaps_handedness();
+
+        let faces = obj.faces.iter().map(|face| {
+            // mirroring flips the winding, so swap two corners to keep the
+            // face normal on the same side as the vertex normals
+            let [a, b, c] = face.vertices;
+            let [a, b, c] = if mirror { [a, c, b] } else { [a, b, c] };
+
+            MeshFace {
+                positions: [a.v as u32, b.v as u32, c.v as u32],
+                normals: match (a.vn, b.vn, c.vn) {
+                    (Some(n0), Some(n1), Some(n2)) => Some([n0 as u32, n1 as u32, n2 as u32]),
+                    _ => None,
+                },
+                uvs: match (a.vt, b.vt, c.vt) {
+                    (Some(t0), Some(t1), Some(t2)) => Some([t0 as u32, t1 as u32, t2 as u32]),
+                    _ => None,
+                },
+                material: face.material,
+            }
+        }).collect();
+
+        Mesh {
+            positions: obj.positions.iter().map(|p| transform.point(*p)).collect(),
+            normals: obj.normals.iter().map(|n| transform.normal(*n)).collect(),
+            uvs: obj.uvs.clone(),
+            faces,
+        }
+    }
+
+    /// References to every face, numbered in order starting at `first_id`.
+    pub fn triangles(mesh: &Arc<Mesh>, first_id: usize) -> Vec<MeshTriangle> {
+        (0..mesh.faces.len()).map(|i| MeshTriangle {
+            mesh: mesh.clone(),
+            face: i as u32,
+            id: first_id + i,
+        }).collect()
+    }
+
+    fn corners(&self, face: &MeshFace) -> (Point, Point, Point) {
+        let [a, b, c] = face.positions;
+        (self.positions[a as usize], self.positions[b as usize], self.positions[c as usize])
+    }
+}
+
+/// A triangle of a shared `Mesh`, small enough to copy into many
+/// accelerator leaves.
+#[derive(Debug, Clone)]
+pub struct MeshTriangle {
+    pub mesh: Arc<Mesh>,
+    pub face: u32,
+    pub id: usize,
+}
+
+impl PartialEq for MeshTriangle {
+    fn eq(&self, other: &MeshTriangle) -> bool {
+        Arc::ptr_eq(&self.mesh, &other.mesh) && self.face == other.face && self.id == other.id
+    }
+}
+
+impl Bounded for MeshTriangle {
+    fn bounds(&self) -> Bounds {
+        let (v0, v1, v2) = self.mesh.corners(&self.mesh.faces[self.face as usize]);
+
+        Bounds::new(
+            v0.x.min(v1.x.min(v2.x)),
+            v0.x.max(v1.x.max(v2.x)),
+            v0.y.min(v1.y.min(v2.y)),
+            v0.y.max(v1.y.max(v2.y)),
+            v0.z.min(v1.z.min(v2.z)),
+            v0.z.max(v1.z.max(v2.z)),
+        )
+    }
+}
+
+impl Viewable for MeshTriangle {
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let mesh = &*self.mesh;
+        let face = &mesh.faces[self.face as usize];
+        let (v0, v1, v2) = mesh.corners(face);
+        let (t, u, v) = triangle::moller_trumbore(v0, v1, v2, ray, t_min, t_max)?;
+
+        let normal = v1.vector_to(v0).cross(v2.vector_to(v0)).to_unit();
+        let shading = match face.normals {
+            None => normal,
+            Some([n0, n1, n2]) => (
+                mesh.normals[n0 as usize] * (1.0 - u - v)
+                    + mesh.normals[n1 as usize] * u
+                    + mesh.normals[n2 as usize] * v
+            ).to_unit(),
+        };
+        let uv = match face.uvs {
+            None => (u, v),
+            Some([t0, t1, t2]) => {
+                let (t0, t1, t2) = (mesh.uvs[t0 as usize], mesh.uvs[t1 as usize], mesh.uvs[t2 as usize]);
+                (
+                    t0.0 * (1.0 - u - v) + t1.0 * u + t2.0 * v,
+                    t0.1 * (1.0 - u - v) + t1.1 * u + t2.1 * v,
+                )
+            },
+        };
+
+        Some(Hit {
+            t,
+            point: ray.loc.translate(ray.dir * t),
+            normal,
+            shading,
+            uv,
+            prim: self.id,
+            material: face.material,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem;
+    use std::sync::Arc;
+
+    use mesh::{Mesh,MeshTriangle};
+    use obj;
+    use geometry::Viewable;
+    use triangle::Triangle;
+    use transform::Transform;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_matches_triangle() {
+        let data = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvn 0 0 1\nvn 1 0 0\nvn 0 1 0\nf 1/1/1 2/2/2 3/3/3\n";
+        let mesh = Arc::new(Mesh::from_obj(&obj::parse(data.as_bytes()).unwrap(), &Transform::identity()));
+        let triangles = Mesh::triangles(&mesh, 7);
+        let triangle = Triangle {
+            uvs: Some([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]),
+            id: 7,
+            ..Triangle::with_normals(
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                [Vector::new(0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)],
+            )
+        };
+        let ray = Ray::new(Point::new(0.5, 0.25, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert!(triangles.len() == 1);
+        assert!(triangles[0].intersects(ray, 0.0, 100.0) == triangle.intersects(ray, 0.0, 100.0));
+        assert!(triangles[0].intersects(ray, 0.0, 1.0).is_none());
+
+        // the point of sharing the buffers
+        assert!(mem::size_of::<MeshTriangle>() * 4 < mem::size_of::<Triangle>());
+    }
+}
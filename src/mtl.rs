@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::prelude::*;
+use std::path::{Path,PathBuf};
+
+use color::Color;
+use material::BasicMaterial;
+use obj::{parse_float,rest,ObjError,ObjErrorKind};
+
+/// One `newmtl` entry of a Wavefront MTL file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Color,
+    /// `Ks`
+    pub specular: Color,
+    /// `Ns`, the Phong exponent.
+    pub shininess: f64,
+    /// `Ke`
+    pub emission: Color,
+    /// `d`, or one minus `Tr`.
+    pub dissolve: f64,
+    /// `Ni`
+    pub ior: f64,
+    /// `illum`, the lighting model.
+    pub illum: u32,
+    /// `map_Kd`
+    pub diffuse_map: Option<PathBuf>,
+    /// `map_Bump` or `bump`
+    pub bump_map: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+
+    pub fn new(name: String) -> MtlMaterial {
+        MtlMaterial {
+            name,
+            diffuse: Color::white(),
+            specular: Color::black(),
+            shininess: 0.0,
+            emission: Color::black(),
+            dissolve: 1.0,
+            ior: 1.0,
+            illum: 2,
+            diffuse_map: None,
+            bump_map: None,
+        }
+    }
+
+    /// The closest `BasicMaterial`, ignoring texture maps.
+    pub fn to_basic(&self) -> BasicMaterial {
+        BasicMaterial {
+            albedo: self.diffuse,
+            // illumination models 0 and 1 have no highlights
+            specular: if self.illum < 2 { Color::black() } else { self.specular },
+            // a common conversion from Phong exponents to roughness
+            roughness: (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt(),
+            emission: self.emission,
+            // models 3 and up add ray traced reflections
+            reflection: if self.illum < 3 { Color::black() } else { self.specular },
+            transmission: 1.0 - self.dissolve.clamp(0.0, 1.0),
+            ior: self.ior,
+        }
+    }
+}
+
+/// Reads a material library, resolving texture paths relative to it.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Vec<MtlMaterial>, ObjError> {
+    let path = path.as_ref();
+    let mut materials = parse(BufReader::new(File::open(path)?))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    for material in materials.iter_mut() {
+        material.diffuse_map = material.diffuse_map.take().map(|map| dir.join(map));
+        material.bump_map = material.bump_map.take().map(|map| dir.join(map));
+    }
+
+    Ok(materials)
+}
+
+pub fn parse<R: BufRead>(reader: R) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials: Vec<MtlMaterial> = vec![];
+
+    for (i, line) in reader.lines().enumerate() {
+        let n = i + 1;
+        let line = line.map_err(|e| ObjError::new(n, ObjErrorKind::Io(e)))?;
+        let line = match line.find('#') {
+            Some(start) => &line[..start],
+            None => &line[..],
+        };
+
+        let mut entries = line.split_whitespace();
+        let statement = match entries.next() {
+            Some(statement) => statement,
+            None => continue,
+        };
+
+        let err = |kind| ObjError::new(n, kind);
+
+        if statement == "newmtl" {
+            materials.push(MtlMaterial::new(rest(line, statement)));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(err(ObjErrorKind::NoMaterial(statement.to_string()))),
+        };
+
+        match statement {
+            "Kd" => material.diffuse = parse_color(statement, entries).map_err(err)?,
+            "Ks" => material.specular = parse_color(statement, entries).map_err(err)?,
+            "Ke" => material.emission = parse_color(statement, entries).map_err(err)?,
+            "Ns" => material.shininess = parse_float(statement, entries.next()).map_err(err)?,
+            "Ni" => material.ior = parse_float(statement, entries.next()).map_err(err)?,
+            "d" => material.dissolve = parse_float(statement, entries.next()).map_err(err)?,
+            "Tr" => material.dissolve = 1.0 - parse_float(statement, entries.next()).map_err(err)?,
+            "illum" => {
+                material.illum = parse_float(statement, entries.next()).map_err(err)? as u32;
+            },
+            // options like `-s 1 1 1` come first, so the file is last
+            "map_Kd" => material.diffuse_map = Some(parse_map(statement, entries).map_err(err)?),
+            "map_Bump" | "map_bump" | "bump" => {
+                material.bump_map = Some(parse_map(statement, entries).map_err(err)?);
+            },
+            _ => continue, // ambient color, other maps and so on aren't used
+        }
+    }
+
+    Ok(materials)
+}
+
+/// `r g b`, or a single value for a grey.
+fn parse_color<'a, I>(statement: &str, mut entries: I) -> Result<Color, ObjErrorKind> where I: Iterator<Item=&'a str> {
+    let r = parse_float(statement, entries.next())?;
+    let g = match entries.next() {
+        Some(g) => parse_float(statement, Some(g))?,
+        None => return Ok(Color::new(r, r, r)),
+    };
+    let b = parse_float(statement, entries.next())?;
+
+    Ok(Color::new(r, g, b))
+}
+
+fn parse_map<'a, I>(statement: &str, entries: I) -> Result<PathBuf, ObjErrorKind> where I: Iterator<Item=&'a str> {
+    match entries.last() {
+        Some(file) => Ok(PathBuf::from(file)),
+        None => Err(ObjErrorKind::MissingValue(statement.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use mtl::{parse,MtlMaterial};
+    use color::Color;
+
+    #[test]
+    fn test_parse() {
+        let data = "\
+# Blender MTL File
+newmtl Skin
+Ns 198.0
+Ka 1.0 1.0 1.0
+Kd 0.8 0.4 0.2
+Ks 0.5 0.5 0.5
+Ke 0.0 0.0 0.1
+Ni 1.45
+d 0.75
+illum 2
+map_Kd -s 1 1 1 skin.png
+map_Bump bump.png
+
+newmtl Glass
+Kd 0.5
+Ks 1
+Tr 0.9
+illum 7
+";
+        let materials = parse(data.as_bytes()).unwrap();
+
+        assert!(materials.len() == 2);
+        assert!(materials[0] == MtlMaterial {
+            name: "Skin".to_string(),
+            diffuse: Color::new(0.8, 0.4, 0.2),
+            specular: Color::new(0.5, 0.5, 0.5),
+            shininess: 198.0,
+            emission: Color::new(0.0, 0.0, 0.1),
+            dissolve: 0.75,
+            ior: 1.45,
+            illum: 2,
+            diffuse_map: Some(PathBuf::from("skin.png")),
+            bump_map: Some(PathBuf::from("bump.png")),
+        });
+        assert!(materials[1].diffuse == Color::new(0.5, 0.5, 0.5));
+        assert!(materials[1].illum == 7);
+
+        let glass = materials[1].to_basic();
+        assert!((glass.transmission - 0.9).abs() < 1e-9);
+        assert!(glass.roughness == 1.0);
+        assert!(glass.reflection == Color::white());
+        assert!(materials[0].to_basic().reflection == Color::black());
+        assert!((materials[0].to_basic().roughness - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse("Kd 1 1 1\n".as_bytes()).unwrap_err();
+        assert!(format!("{}", err) == "line 1: 'Kd' comes before any newmtl");
+
+        let err = parse("newmtl a\nKd 1 x 1\n".as_bytes()).unwrap_err();
+        assert!(format!("{}", err) == "line 2: 'x' is not a number");
+    }
+}
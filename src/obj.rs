@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::prelude::*;
+use std::str::FromStr;
+
+use point::Point;
+use vector::Vector;
+
+/// One corner of a face, as zero based indices into the `Obj` buffers.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ObjVertex {
+    pub v: usize,
+    pub vt: Option<usize>,
+    pub vn: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ObjFace {
+    pub vertices: [ObjVertex; 3],
+    /// Index into `Obj::objects` of the last `o` statement.
+    pub object: Option<usize>,
+    /// Index into `Obj::groups` of the last `g` statement.
+    pub group: Option<usize>,
+    /// Smoothing group, `0` when smoothing is off.
+    pub smoothing: u32,
+    /// Index into `Obj::materials` of the last `usemtl` statement.
+    pub material: Option<usize>,
+}
+
+/// Contents of a Wavefront OBJ file. Faces with more than three vertices
+/// are split into triangles.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Obj {
+    pub positions: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub uvs: Vec<(f64, f64)>,
+    pub faces: Vec<ObjFace>,
+    pub objects: Vec<String>,
+    pub groups: Vec<String>,
+    /// Material names, in the order they are first used.
+    pub materials: Vec<String>,
+    /// Material libraries named by `mtllib` statements.
+    pub libraries: Vec<String>,
+}
+
+impl Obj {
+
+    /// Gives every face corner without a normal a smooth one, averaged from
+    /// the faces around that vertex in the same smoothing group whose
+    /// normals are within `max_angle` degrees of the face's own. Sharper
+    /// edges than that, and faces with smoothing off, stay faceted. Files
+    /// that don't use smoothing groups at all are smoothed as one group.
+    pub fn generate_normals(&mut self, max_angle: f64) {
+        let min_cos = max_angle.to_radians().cos();
+
+        // unnormalized, so bigger faces count for more in the average
+        let face_normals: Vec<Vector> = self.faces.iter().map(|face| {
+            let v0 = self.positions[face.vertices[0].v];
+            let v1 = self.positions[face.vertices[1].v];
+            let v2 = self.positions[face.vertices[2].v];
+            v1.vector_to(v0).cross(v2.vector_to(v0))
+        }).collect();
+
+        let mut adjacent: Vec<Vec<usize>> = vec![vec![]; self.positions.len()];
+        for (i, face) in self.faces.iter().enumerate() {
+            for vertex in face.vertices.iter() {
+                adjacent[vertex.v].push(i);
+            }
+        }
+
+        // plenty of exporters write a single `s off` whether or not the
+        // model is meant to be smooth
+        let grouped = self.faces.iter().any(|face| face.smoothing != 0);
+        let group = |face: &ObjFace| if grouped { face.smoothing } else { 1 };
+
+        // normals already generated for each vertex and smoothing group
+        let mut generated: HashMap<(usize, u32), Vec<usize>> = HashMap::new();
+
+        for (i, n) in face_normals.iter().enumerate() {
+            let smoothing = group(&self.faces[i]);
+            if smoothing == 0 || n.mag() == 0.0 {
+                continue;
+            }
+
+            let unit = n.to_unit();
+
+            for corner in 0..3 {
+                let vertex = self.faces[i].vertices[corner];
+                if vertex.vn.is_some() {
+                    continue;
+                }
+
+                let faces = &self.faces;
+                let normal = adjacent[vertex.v].iter()
+                    .filter(|&&j| group(&faces[j]) == smoothing)
+                    .map(|&j| face_normals[j])
+                    .filter(|other| other.mag() > 0.0 && other.to_unit().dot(unit) >= min_cos)
+                    .fold(Vector::new(0.0, 0.0, 0.0), |sum, other| sum + other)
+                    .to_unit();
+
+                let indices = generated.entry((vertex.v, smoothing)).or_default();
+                let index = match indices.iter().find(|&&index| self.normals[index] == normal) {
+                    Some(&index) => index,
+                    None => {
+                        self.normals.push(normal);
+                        indices.push(self.normals.len() - 1);
+                        self.normals.len() - 1
+                    },
+                };
+                self.faces[i].vertices[corner].vn = Some(index);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjErrorKind {
+    Io(io::Error),
+    MissingValue(String),
+    InvalidNumber(String),
+    InvalidIndex(String),
+    TooFewVertices,
+    /// A material property before any `newmtl` statement.
+    NoMaterial(String),
+}
+
+#[derive(Debug)]
+pub struct ObjError {
+    /// One based line the error occurred on, `0` if it isn't tied to a line.
+    pub line: usize,
+    pub kind: ObjErrorKind,
+}
+
+impl ObjError {
+
+    pub fn new(line: usize, kind: ObjErrorKind) -> ObjError {
+        ObjError {
+            line,
+            kind,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> ObjError {
+        ObjError::new(0, ObjErrorKind::Io(err))
+    }
+}
+
+impl fmt::Display for ObjErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ObjErrorKind::Io(ref err) => write!(f, "{}", err),
+            ObjErrorKind::MissingValue(ref statement) => write!(f, "'{}' is missing a value", statement),
+            ObjErrorKind::InvalidNumber(ref value) => write!(f, "'{}' is not a number", value),
+            ObjErrorKind::InvalidIndex(ref value) => write!(f, "'{}' does not refer to an existing element", value),
+            ObjErrorKind::TooFewVertices => write!(f, "faces need at least three vertices"),
+            ObjErrorKind::NoMaterial(ref statement) => write!(f, "'{}' comes before any newmtl", statement),
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.kind),
+            line => write!(f, "line {}: {}", line, self.kind),
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+pub fn parse<R: BufRead>(reader: R) -> Result<Obj, ObjError> {
+    let mut obj = Obj::default();
+    let mut object = None;
+    let mut group = None;
+    let mut smoothing = 0;
+    let mut material = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let n = i + 1;
+        let line = line.map_err(|e| ObjError::new(n, ObjErrorKind::Io(e)))?;
+        let line = match line.find('#') {
+            Some(start) => &line[..start],
+            None => &line[..],
+        };
+
+        let mut entries = line.split_whitespace();
+        let statement = match entries.next() {
+            Some(statement) => statement,
+            None => continue,
+        };
+
+        let err = |kind| ObjError::new(n, kind);
+
+        match statement {
+            "v" => {
+                let x = parse_float(statement, entries.next()).map_err(err)?;
+                let y = parse_float(statement, entries.next()).map_err(err)?;
+                let z = parse_float(statement, entries.next()).map_err(err)?;
+                obj.positions.push(Point::new(x, y, z));
+            },
+            "vn" => {
+                let x = parse_float(statement, entries.next()).map_err(err)?;
+                let y = parse_float(statement, entries.next()).map_err(err)?;
+                let z = parse_float(statement, entries.next()).map_err(err)?;
+                obj.normals.push(Vector::new(x, y, z));
+            },
+            "vt" => {
+                let u = parse_float(statement, entries.next()).map_err(err)?;
+                let v = match entries.next() {
+                    Some(v) => parse_float(statement, Some(v)).map_err(err)?,
+                    None => 0.0,
+                };
+                obj.uvs.push((u, v));
+            },
+            "f" => {
+                let vertices = entries
+                    .map(|entry| parse_vertex(entry, &obj))
+                    .collect::<Result<Vec<ObjVertex>, _>>()
+                    .map_err(err)?;
+
+                if vertices.len() < 3 {
+                    return Err(err(ObjErrorKind::TooFewVertices));
+                }
+
+                // fan out from the first vertex, fine for the convex
+                // polygons modelling tools export
+                for j in 1..vertices.len() - 1 {
+                    obj.faces.push(ObjFace {
+                        vertices: [vertices[0], vertices[j], vertices[j + 1]],
+                        object,
+                        group,
+                        smoothing,
+                        material,
+                    });
+                }
+            },
+            "o" => {
+                obj.objects.push(rest(line, statement));
+                object = Some(obj.objects.len() - 1);
+            },
+            "g" => {
+                obj.groups.push(rest(line, statement));
+                group = Some(obj.groups.len() - 1);
+            },
+            "s" => {
+                smoothing = match entries.next() {
+                    Some("off") => 0,
+                    Some(value) => u32::from_str(value)
+                        .map_err(|_| err(ObjErrorKind::InvalidNumber(value.to_string())))?,
+                    None => return Err(err(ObjErrorKind::MissingValue(statement.to_string()))),
+                };
+            },
+            "usemtl" => {
+                let name = rest(line, statement);
+                material = match obj.materials.iter().position(|m| *m == name) {
+                    Some(index) => Some(index),
+                    None => {
+                        obj.materials.push(name);
+                        Some(obj.materials.len() - 1)
+                    },
+                };
+            },
+            "mtllib" => {
+                for library in entries {
+                    obj.libraries.push(library.to_string());
+                }
+            },
+            _ => continue, // curves, lines and points can't be rendered
+        }
+    }
+
+    Ok(obj)
+}
+
+/// Everything after the statement keyword, used for names.
+pub(crate) fn rest(line: &str, statement: &str) -> String {
+    line.trim_start()[statement.len()..].trim().to_string()
+}
+
+pub(crate) fn parse_float(statement: &str, value: Option<&str>) -> Result<f64, ObjErrorKind> {
+    match value {
+        Some(value) => f64::from_str(value).map_err(|_| ObjErrorKind::InvalidNumber(value.to_string())),
+        None => Err(ObjErrorKind::MissingValue(statement.to_string())),
+    }
+}
+
+/// Resolves a one based (or negative, relative to the end) index into a
+/// buffer of length `len`.
+fn parse_index(value: &str, len: usize) -> Result<usize, ObjErrorKind> {
+    let index = i64::from_str(value).map_err(|_| ObjErrorKind::InvalidNumber(value.to_string()))?;
+    let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(ObjErrorKind::InvalidIndex(value.to_string()));
+    }
+
+    Ok(resolved as usize)
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_vertex(entry: &str, obj: &Obj) -> Result<ObjVertex, ObjErrorKind> {
+    let mut parts = entry.split('/');
+    let v = parse_index(parts.next().unwrap_or(""), obj.positions.len())?;
+    let vt = match parts.next() {
+        Some("") | None => None,
+        Some(value) => Some(parse_index(value, obj.uvs.len())?),
+    };
+    let vn = match parts.next() {
+        Some("") | None => None,
+        Some(value) => Some(parse_index(value, obj.normals.len())?),
+    };
+
+    Ok(ObjVertex {
+        v,
+        vt,
+        vn,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use obj::{parse,ObjErrorKind,ObjVertex};
+    use point::Point;
+    use vector::Vector;
+
+    const CUBE_SIDE: &str = "
+# a square made from two triangles
+mtllib side.mtl
+o Side
+
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g front
+usemtl Red
+s 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn test_parse() {
+        let obj = parse(CUBE_SIDE.as_bytes()).unwrap();
+
+        assert!(obj.positions.len() == 4);
+        assert!(obj.positions[2] == Point::new(1.0, 1.0, 0.0));
+        assert!(obj.normals == vec![Vector::new(0.0, 0.0, 1.0)]);
+        assert!(obj.uvs[2] == (1.0, 1.0));
+        assert!(obj.objects == vec!["Side".to_string()]);
+        assert!(obj.groups == vec!["front".to_string()]);
+        assert!(obj.materials == vec!["Red".to_string()]);
+        assert!(obj.libraries == vec!["side.mtl".to_string()]);
+
+        // quad is split into a fan of two triangles
+        assert!(obj.faces.len() == 2);
+        assert!(obj.faces[1].vertices[0] == ObjVertex { v: 0, vt: Some(0), vn: Some(0) });
+        assert!(obj.faces[1].vertices[2] == ObjVertex { v: 3, vt: Some(3), vn: Some(0) });
+        assert!(obj.faces[1].smoothing == 1);
+        assert!(obj.faces[1].material == Some(0));
+        assert!(obj.faces[1].group == Some(0));
+    }
+
+    #[test]
+    fn test_index_forms() {
+        let obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//1 -2//-1 3\n".as_bytes()).unwrap();
+        let face = obj.faces[0];
+
+        assert!(face.vertices[0] == ObjVertex { v: 0, vt: None, vn: Some(0) });
+        assert!(face.vertices[1] == ObjVertex { v: 1, vt: None, vn: Some(0) });
+        assert!(face.vertices[2] == ObjVertex { v: 2, vt: None, vn: None });
+    }
+
+    #[test]
+    fn test_generate_normals() {
+        // two faces folded 90 degrees along the edge from 1 to 2
+        let mut obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\ns 1\nf 1 2 3\nf 2 1 4\n".as_bytes()).unwrap();
+        let mut sharp = obj.clone();
+
+        obj.generate_normals(100.0);
+        let shared = obj.normals[obj.faces[0].vertices[0].vn.unwrap()];
+        let corner = obj.normals[obj.faces[0].vertices[2].vn.unwrap()];
+        assert!((shared - Vector::new(0.0, 1.0, 1.0).to_unit()).mag() < 1e-9);
+        assert!(corner == Vector::new(0.0, 0.0, 1.0));
+
+        sharp.generate_normals(60.0);
+        let shared = sharp.normals[sharp.faces[0].vertices[0].vn.unwrap()];
+        assert!(shared == Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_smoothing_groups() {
+        // a flat strip of two faces in group 1, then a folded face with
+        // smoothing off sharing the edge from 1 to 2
+        let mut obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 0 0 1\n\
+            s 1\nf 1 2 3\nf 2 4 3\ns off\nf 2 1 5\n".as_bytes()).unwrap();
+        obj.generate_normals(180.0);
+
+        // the strip's shared vertices share one normal, untouched by the fold
+        assert!(obj.faces[0].vertices[1].vn == obj.faces[1].vertices[0].vn);
+        assert!(obj.faces[0].vertices[2].vn == obj.faces[1].vertices[2].vn);
+        assert!(obj.normals.len() == 4);
+        assert!(obj.normals.iter().all(|&n| n == Vector::new(0.0, 0.0, 1.0)));
+
+        // and the face with smoothing off keeps its flat normal
+        assert!(obj.faces[2].vertices.iter().all(|vertex| vertex.vn.is_none()));
+    }
+
+    #[test]
+    fn test_smoothing_off_everywhere() {
+        // a file that only ever says `s off` is smoothed by angle alone
+        let mut obj = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\ns off\nf 1 2 3\nf 2 1 4\n".as_bytes()).unwrap();
+        obj.generate_normals(100.0);
+
+        let shared = obj.normals[obj.faces[0].vertices[0].vn.unwrap()];
+        assert!((shared - Vector::new(0.0, 1.0, 1.0).to_unit()).mag() < 1e-9);
+        assert!(obj.faces[0].vertices[0].vn == obj.faces[1].vertices[1].vn);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n".as_bytes()).unwrap_err();
+        assert!(err.line == 4);
+        match err.kind { ObjErrorKind::InvalidIndex(ref v) => assert!(v == "3"), _ => panic!() }
+
+        let err = parse("v 0 zero 0\n".as_bytes()).unwrap_err();
+        assert!(err.line == 1);
+        match err.kind { ObjErrorKind::InvalidNumber(ref v) => assert!(v == "zero"), _ => panic!() }
+
+        let err = parse("v 0 0\n".as_bytes()).unwrap_err();
+        match err.kind { ObjErrorKind::MissingValue(ref v) => assert!(v == "v"), _ => panic!() }
+
+        let err = parse("v 0 0 0\nf 1 1\n".as_bytes()).unwrap_err();
+        match err.kind { ObjErrorKind::TooFewVertices => (), _ => panic!() }
+
+        let err = parse("v 0 0 0\nf 0 1 1\n".as_bytes()).unwrap_err();
+        match err.kind { ObjErrorKind::InvalidIndex(ref v) => assert!(v == "0"), _ => panic!() }
+    }
+}
@@ -1,24 +1,25 @@
 use std::iter::FromIterator;
 use std::fmt::Debug;
 
-use geometry::{Bounded, Viewable};
+use geometry::{Bounded, Hit, Viewable};
+use accelerator::Accelerator;
 use bounds::Bounds;
 use ray::Ray;
 
 #[derive(Debug)]
-pub struct Octree<T> where T: Bounded + Viewable + Copy + Debug {
+pub struct Octree<T> where T: Bounded + Viewable + Clone + Debug {
     depth: u8,
     bounds: Bounds,
     faces: Vec<T>,
     trees: Vec<Octree<T>>,
 }
 
-impl<T> Octree<T> where T: Bounded + Viewable + Copy + Debug {
+impl<T> Octree<T> where T: Bounded + Viewable + Clone + Debug {
 
     pub fn new(depth: u8, bounds: Bounds) -> Octree<T> {
         Octree {
-            depth: depth,
-            bounds: bounds,
+            depth,
+            bounds,
             faces: vec![],
             trees: vec![],
         }
@@ -32,38 +33,91 @@ impl<T> Octree<T> where T: Bounded + Viewable + Copy + Debug {
         }
 
         // case: not branched
-        if self.trees.len() == 0 {
+        if self.trees.is_empty() {
             self.subdivide();
             self.insert(t);
             return;
         }
 
         // case: add
-        for mut tree in self.trees.iter_mut() {
+        for tree in self.trees.iter_mut() {
 
             if tree.overlaps(t.bounds()) {
-                tree.insert(t);
+                tree.insert(t.clone());
             }
         }
     }
 
-    pub fn get_faces(&self, ray: Ray) -> Vec<T> {
-        let intersects = self.bounds.intersects(ray).is_some();
-        let mut all: Vec<T> = vec![];
-
-        if self.depth == 0 && intersects {
-            return self.faces.clone();
+    /// Closest hit along `ray` between `t_min` and `t_max`.
+    pub fn intersect(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        match self.bounds.intersects(ray) {
+            Some((enter, exit)) if enter <= t_max && exit >= t_min => (),
+            _ => return None,
         }
 
-        if intersects {
-            for tree in self.trees.iter() {
-                for t in tree.get_faces(ray).iter() {
-                    all.push(*t);
+        // children are numbered x | y << 1 | z << 2, so flipping the bits of
+        // the axes the ray runs backwards along visits them front to back
+        let mask = (ray.dir.x < 0.0) as usize
+            | ((ray.dir.y < 0.0) as usize) << 1
+            | ((ray.dir.z < 0.0) as usize) << 2;
+
+        self.closest(ray, t_min, t_max, mask)
+    }
+
+    fn closest(&self, ray: Ray, t_min: f64, t_max: f64, mask: usize) -> Option<Hit> {
+        let mut closest = None;
+        let mut t_max = t_max;
+
+        if self.depth == 0 {
+            for face in self.faces.iter() {
+                if let Some(hit) = face.intersects(ray, t_min, t_max) {
+                    t_max = hit.t;
+                    closest = Some(hit);
                 }
             }
+
+            return closest;
         }
 
-        all
+        for i in 0..self.trees.len() {
+            let tree = &self.trees[i ^ mask];
+            let (enter, exit) = match tree.bounds.intersects(ray) {
+                Some(range) if range.1 >= t_min => range,
+                _ => continue,
+            };
+
+            // every cell after this one starts even further along the ray
+            if enter > t_max {
+                break;
+            }
+
+            if let Some(hit) = tree.closest(ray, t_min, t_max, mask) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+
+            // a hit inside this cell can't be beaten by the ones behind it
+            if closest.is_some() && t_max <= exit {
+                break;
+            }
+        }
+
+        closest
+    }
+
+    /// Whether anything in the tree blocks `ray` between `t_min` and
+    /// `t_max`. Stops looking as soon as one blocker is found.
+    pub fn occluded(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        match self.bounds.intersects(ray) {
+            Some((enter, exit)) if enter <= t_max && exit >= t_min => (),
+            _ => return false,
+        }
+
+        if self.depth == 0 {
+            return self.faces.iter().any(|face| face.intersects(ray, t_min, t_max).is_some());
+        }
+
+        self.trees.iter().any(|tree| tree.occluded(ray, t_min, t_max))
     }
 
     fn subdivide(&mut self) {
@@ -93,7 +147,23 @@ impl<T> Octree<T> where T: Bounded + Viewable + Copy + Debug {
     }
 }
 
-impl<T> FromIterator<T> for Octree<T> where T: Bounded + Viewable + Copy + Debug {
+impl<T> Bounded for Octree<T> where T: Bounded + Viewable + Clone + Debug {
+    fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+}
+
+impl<T> Accelerator for Octree<T> where T: Bounded + Viewable + Clone + Debug + Send + Sync {
+    fn intersect(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        Octree::intersect(self, ray, t_min, t_max)
+    }
+
+    fn occluded(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        Octree::occluded(self, ray, t_min, t_max)
+    }
+}
+
+impl<T> FromIterator<T> for Octree<T> where T: Bounded + Viewable + Clone + Debug {
     fn from_iter<I>(iter: I) -> Self  where I: IntoIterator<Item=T> {
         let items: Vec<T> = iter.into_iter().collect();
         let bounds = items.iter().fold(Bounds::zero(), |sum, t| sum + t.bounds());
@@ -113,13 +183,63 @@ impl<T> FromIterator<T> for Octree<T> where T: Bounded + Viewable + Copy + Debug
 mod test {
     use octree::Octree;
     use bounds::Bounds;
+    use triangle::Triangle;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+    use geometry::{Hit,Viewable};
+    use sampler::Sampler;
 
     #[test]
     fn test_creation() {
         let bounds = Bounds::new(0.0, 1.0, 0.0, 1.0, 0.0, 1.0);
-        let tree = Octree::new(bounds, 1);
-        println!("{:?}", tree);
+        let tree: Octree<Triangle> = Octree::new(1, bounds);
+
+        assert!(tree.bounds == bounds);
+        assert!(tree.faces.is_empty() && tree.trees.is_empty());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let mut sampler = Sampler::new(2);
+        let mut random_point = || Point::new(sampler.next_f64() * 10.0, sampler.next_f64() * 10.0, sampler.next_f64() * 10.0);
+
+        let triangles: Vec<Triangle> = (0..200).map(|i| {
+            let a = random_point();
+            let (b, c) = (random_point(), random_point());
+            Triangle {
+                id: i,
+                ..Triangle::new(a, a.translate(b.vector_to(Point::zero()) * 0.1), a.translate(c.vector_to(Point::zero()) * 0.1))
+            }
+        }).collect();
+        let tree: Octree<Triangle> = triangles.iter().cloned().collect();
+
+        for _ in 0..500 {
+            let from = random_point();
+            let ray = Ray::new(from, random_point().vector_to(from));
 
-        assert!(false);
+            let expected = triangles.iter().fold(None, |closest: Option<Hit>, t| {
+                let t_max = closest.map_or(f64::INFINITY, |hit| hit.t);
+                t.intersects(ray, 0.0, t_max).or(closest)
+            });
+
+            assert!(tree.intersect(ray, 0.0, f64::INFINITY).map(|h| h.prim) == expected.map(|h| h.prim));
+        }
+    }
+
+    #[test]
+    fn test_occluded() {
+        let tree: Octree<Triangle> = vec![
+            Triangle::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0), Point::new(0.0, 1.0, 0.0)),
+            Triangle::new(Point::new(0.0, 0.0, 1.0), Point::new(1.0, 0.0, 1.0), Point::new(0.0, 1.0, 1.0)),
+        ].into_iter().collect();
+        let ray = Ray::new(Point::new(0.25, 0.25, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert!(tree.occluded(ray, 0.0, 10.0));
+        assert!(tree.occluded(ray, 0.0, 1.5));
+        assert!(!tree.occluded(ray, 0.0, 0.5));
+
+        let miss = Ray::new(Point::new(2.0, 2.0, 2.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(!tree.occluded(miss, 0.0, 10.0));
     }
 }
@@ -0,0 +1,97 @@
+use geometry::{Hit,Viewable};
+use point::Point;
+use vector::Vector;
+use ray::Ray;
+
+/// An infinite plane. It has no bounds, so it can't go in an
+/// `Accelerator` and scenes test it on its own.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Plane {
+    pub point: Point,
+    pub normal: Vector,
+    pub id: usize,
+    pub material: Option<usize>,
+}
+
+impl Plane {
+
+    pub fn new(point: Point, normal: Vector) -> Plane {
+        Plane {
+            point,
+            normal: normal.to_unit(),
+            id: 0,
+            material: None,
+        }
+    }
+
+    /// Two unit vectors along the plane, at right angles to each other.
+    fn tangents(&self) -> (Vector, Vector) {
+        let axis = if self.normal.x.abs() < 0.9 {
+            Vector::new(1.0, 0.0, 0.0)
+        } else {
+            Vector::new(0.0, 1.0, 0.0)
+        };
+        let s = axis.cross(self.normal).to_unit();
+
+        (s, self.normal.cross(s))
+    }
+}
+
+impl Viewable for Plane {
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let denom = self.normal.dot(ray.dir);
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+
+        let t = self.point.vector_to(ray.loc).dot(self.normal) / denom;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        // world distances from `point` along the plane
+        let point = ray.loc.translate(ray.dir * t);
+        let offset = point.vector_to(self.point);
+        let (s, r) = self.tangents();
+
+        Some(Hit {
+            t,
+            point,
+            normal: self.normal,
+            shading: self.normal,
+            uv: (offset.dot(s), offset.dot(r)),
+            prim: self.id,
+            material: self.material,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use plane::Plane;
+    use geometry::Viewable;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_intersects() {
+        let plane = Plane::new(Point::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 2.0));
+        let ray = Ray::new(Point::new(3.0, 4.0, 1.0), Vector::new(0.0, 0.0, -1.0));
+
+        let hit = plane.intersects(ray, 0.0, 100.0).unwrap();
+        assert!(hit.t == 2.0);
+        assert!(hit.point == Point::new(3.0, 4.0, -1.0));
+        assert!(hit.normal == Vector::new(0.0, 0.0, 1.0));
+        assert!(hit.uv.0.abs() + hit.uv.1.abs() == 7.0);
+
+        // from below, too far, parallel and pointing away
+        let below = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(1.0, 0.0, 1.0));
+        assert!(plane.intersects(below, 0.0, 100.0).is_some());
+        assert!(plane.intersects(ray, 0.0, 1.0).is_none());
+        let parallel = Ray::new(Point::new(0.0, 0.0, 1.0), Vector::new(1.0, 1.0, 0.0));
+        assert!(plane.intersects(parallel, 0.0, 100.0).is_none());
+        let away = Ray::new(Point::new(0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(plane.intersects(away, 0.0, 100.0).is_none());
+    }
+}
@@ -1,6 +1,5 @@
 use std::ops::{Sub,Add};
 use vector::Vector;
-use std::str::FromStr;
 
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -14,9 +13,9 @@ impl Point {
 
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         Point {
-            x: x,
-            y: y,
-            z: z,
+            x,
+            y,
+            z,
         }
     }
 
@@ -28,17 +27,6 @@ impl Point {
         }
     }
 
-    pub fn from_str(line: &str) -> Point {
-        let mut entries = line.split_whitespace();
-        entries.next();
-
-        let x = f64::from_str(entries.next().unwrap()).unwrap();
-        let y = f64::from_str(entries.next().unwrap()).unwrap();
-        let z = f64::from_str(entries.next().unwrap()).unwrap();
-
-        Point::new(x, y, z)
-    }
-
     pub fn distance_to(self, p: Point) -> f64 {
         let diff = self - p;
         let sum = diff.x.powi(2) + diff.y.powi(2) + diff.z.powi(2);
@@ -10,9 +10,11 @@ pub struct Ray {
 
 impl Ray {
     pub fn new(loc: Point, dir: Vector) -> Ray {
+        let dir = dir.to_unit();
+
         Ray {
-            loc: loc,
-            dir: dir.to_unit(),
+            loc,
+            dir,
             inv: dir.invert(),
         }
     }
@@ -1,19 +1,53 @@
-use image::{DynamicImage,Luma};
+use std::error::Error;
+use std::fmt;
 use std::thread;
 use std::thread::JoinHandle;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use num_cpus;
 
-use geometry::{Viewable};
+use geometry::Hit;
+use ray::Ray;
+use vector::Vector;
 use scene::Scene;
+use sampler::Sampler;
+use color::Color;
+use film::Film;
 
-const IMGX: u32 = 1000;
-const IMGY: u32 = 1000;
-const SCALE: f64 = 0.0025;
+/// How far secondary rays start off the surface they leave, relative to the
+/// size of the hit point's coordinates, so they don't hit that surface again.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    /// Rays traced per pixel. With more than one, rays are jittered
+    /// randomly within the pixel and averaged.
+    pub samples: u32,
+    pub threads: u32,
+    /// Color of pixels whose rays don't hit anything.
+    pub background: Color,
+    /// How many times a ray may bounce before it is cut off.
+    pub max_depth: u32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            width: 1000,
+            height: 1000,
+            samples: 1,
+            threads: num_cpus::get() as u32,
+            background: Color::black(),
+            max_depth: 5,
+        }
+    }
+}
 
 pub fn chunk(w: u32, h: u32, n: u32) -> Vec<Vec<(u32, u32)>> {
     let mut v = vec![];
-    let size = (w + n - 1) / n;
+    let size = w.div_ceil(n);
 
     for i in 0..n {
         let mut pixels = vec![];
@@ -33,37 +67,188 @@ pub fn chunk(w: u32, h: u32, n: u32) -> Vec<Vec<(u32, u32)>> {
     v
 }
 
-pub fn render(scene: Scene) -> DynamicImage {
-    let mut image = DynamicImage::new_luma8(IMGX, IMGY);
-    let cam = scene.camera.unwrap();
-    let rays = Arc::new(cam.rays(IMGX, IMGY, SCALE));
-    let tree = Arc::new(scene.tree);
-    let chunks = num_cpus::get() as u32 * 4;
+/// Color seen along `ray`. Mirrors and glass trace further rays from where
+/// they're hit, until `depth` reaches `settings.max_depth`.
+fn trace(scene: &Scene, ray: Ray, depth: u32, settings: &RenderSettings) -> Color {
+    let hit = match scene.intersect(ray, 0.0, f64::INFINITY) {
+        None => return settings.background,
+        Some(hit) => hit,
+    };
 
-    let results: Result<Vec<Vec<(u32, u32, u8)>>, _> = chunk(IMGX, IMGY, chunks)
-        .into_iter()
-        .map(|chunk| {
-            let tree = tree.clone();
-            let rays = rays.clone();
+    let color = shade(scene, ray, &hit);
+    let material = scene.material(hit.material);
+    let (reflection, transmission) = (material.reflection(), material.transmission());
+
+    if depth >= settings.max_depth || (reflection == Color::black() && transmission == 0.0) {
+        return color;
+    }
+
+    // the shading normal on the side the ray came from
+    let entering = hit.normal.dot(ray.dir) < 0.0;
+    let normal = if entering { hit.shading } else { hit.shading * -1.0 };
+    let mirrored = trace(scene, leave(&hit, ray.dir.reflect(normal)), depth + 1, settings);
+
+    if transmission == 0.0 {
+        return color + reflection * mirrored;
+    }
+
+    // what glass reflects is already counted in its Fresnel split, adding
+    // `reflection` on top would give back more light than came in
+
+    let (n1, n2) = if entering { (1.0, material.ior()) } else { (material.ior(), 1.0) };
+    let through = match ray.dir.refract(normal, n1 / n2) {
+        // total internal reflection
+        None => mirrored,
+        Some(dir) => {
+            let fresnel = schlick(-ray.dir.dot(normal), n1, n2);
+            let refracted = trace(scene, leave(&hit, dir), depth + 1, settings);
+            mirrored * fresnel + refracted * (1.0 - fresnel)
+        },
+    };
+
+    color + through * transmission
+}
+
+/// Ray leaving `hit` in direction `dir`. It starts just off the surface on
+/// the side `dir` points to, pushed along the true normal further the bigger
+/// the hit point's coordinates are, since so is their rounding error.
+fn leave(hit: &Hit, dir: Vector) -> Ray {
+    let p = hit.point;
+    let offset = EPSILON * p.x.abs().max(p.y.abs()).max(p.z.abs()).max(1.0);
+    let side = if hit.normal.dot(dir) < 0.0 { -offset } else { offset };
+    Ray::new(p.translate(hit.normal * side), dir)
+}
+
+/// Fraction of light reflected off the boundary from a medium with index
+/// of refraction `n1` into one with `n2`, at an angle with cosine `cos_i`,
+/// by Schlick's approximation of the Fresnel equations.
+fn schlick(cos_i: f64, n1: f64, n2: f64) -> f64 {
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+
+    // leaving a denser medium, it's the angle on the far side that counts
+    let cos = if n1 > n2 {
+        let sin2_t = (n1 / n2).powi(2) * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return 1.0;
+        }
+        (1.0 - sin2_t).sqrt()
+    } else {
+        cos_i
+    };
+
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+}
+
+/// Color of `hit`: what its material emits plus its reflection of every
+/// light in the scene that it can see, diffuse by Lambert's cosine law and
+/// specular by the Blinn-Phong model.
+fn shade(scene: &Scene, ray: Ray, hit: &Hit) -> Color {
+    // surfaces are two sided, so shade whichever side the ray came from
+    let normal = if hit.normal.dot(ray.dir) > 0.0 { hit.shading * -1.0 } else { hit.shading };
+    let material = scene.material(hit.material);
+
+    // light that goes through the surface isn't scattered off it
+    let diffuse = material.albedo(hit.uv) * (1.0 - material.transmission());
+    let (specular, shininess) = (material.specular(), material.shininess());
+    let view = ray.dir * -1.0;
+
+    scene.lights.iter().fold(material.emission(), |sum, light| {
+        let to_light = light.loc.vector_to(hit.point);
+        let distance = to_light.mag();
+        let l = to_light / distance;
+        let cos = normal.dot(l).max(0.0);
+
+        if cos == 0.0 || scene.occluded(leave(hit, l), 0.0, distance) {
+            return sum;
+        }
+
+        // highlights peak where the normal is halfway between the light
+        // and the viewer. Surfaces rough enough to have a shininess of 0
+        // have none, rather than a highlight spread evenly everywhere.
+        let half = (l + view).to_unit();
+        let n_dot_h = normal.dot(half);
+        let highlight = if n_dot_h > 0.0 && shininess > 0.0 {
+            specular * n_dot_h.powf(shininess)
+        } else {
+            Color::black()
+        };
+
+        sum + (diffuse * cos + highlight) * light.color * light.intensity
+    })
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RenderError {
+    /// The scene has no camera to render from.
+    NoCamera,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderError::NoCamera => write!(f, "the scene has no camera"),
+        }
+    }
+}
+
+impl Error for RenderError {}
+
+pub fn render(mut scene: Scene, settings: &RenderSettings) -> Result<Film, RenderError> {
+    if scene.camera.is_none() {
+        return Err(RenderError::NoCamera);
+    }
+
+    let (w, h) = (settings.width, settings.height);
+    let mut film = Film::new(w, h);
+
+    scene.build();
+    let scene = Arc::new(scene);
+    let settings = *settings;
+    let threads = settings.threads.max(1);
+    let samples = settings.samples.max(1);
+
+    // hand out narrow chunks so threads that finish early can pick up more
+    let chunks = Arc::new(chunk(w, h, threads * 4));
+    let next = Arc::new(AtomicUsize::new(0));
+
+    let results: Result<Vec<Vec<_>>, _> = (0..threads)
+        .map(|_| {
+            let scene = scene.clone();
+            let chunks = chunks.clone();
+            let next = next.clone();
 
             thread::spawn(move || {
-                chunk.into_iter().map(|(x, y)| {
-                    let ray = rays[(x * IMGX + y) as usize];
-                    let min = tree.get_faces(ray).iter().fold(10000.0f64, |min, face| {
-                        match face.intersects(ray) {
-                            None => min,
-                            Some(int) => min.min(ray.loc.distance_to(int))
+                let cam = scene.camera.as_ref().expect("checked before spawning");
+                let mut pixels = vec![];
+
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= chunks.len() { break; }
+
+                    // seed by chunk so the image doesn't depend on scheduling
+                    let mut sampler = Sampler::new(i as u64);
+
+                    for &(x, y) in chunks[i].iter() {
+                        let mut sum = Color::black();
+
+                        for _ in 0..samples {
+                            let (dx, dy) = if samples == 1 {
+                                (0.5, 0.5)
+                            } else {
+                                (sampler.next_f64(), sampler.next_f64())
+                            };
+
+                            let u = (x as f64 + dx - w as f64 / 2.0) / h as f64;
+                            let v = (h as f64 / 2.0 - y as f64 - dy) / h as f64;
+                            let ray = cam.ray(u, v, &mut sampler);
+                            sum = sum + trace(&scene, ray, 0, &settings);
                         }
-                    });
 
-                    let v = match min {
-                        10000.0 => 0u8,
-                        x => 255 - (255.0 / (x - 10.0)) as u8,
-                    };
+                        pixels.push((x, y, sum * (1.0 / samples as f64)));
+                    }
+                }
 
-                   (x, y, v)
-                })
-                .collect()
+                pixels
             })
         })
         .collect::<Vec<JoinHandle<Vec<_>>>>()
@@ -72,11 +257,152 @@ pub fn render(scene: Scene) -> DynamicImage {
         .collect();
 
     for result in results.unwrap().into_iter() {
-        for (x, y, val) in result.into_iter() {
-            let pixel = image.as_mut_luma8().unwrap().get_pixel_mut(x, y);
-            *pixel = Luma([val]);
+        for (x, y, color) in result.into_iter() {
+            film.set(x, y, color);
         };
     }
 
-    image
+    Ok(film)
+}
+
+#[cfg(test)]
+mod test {
+    use render::{leave,render,schlick,shade,trace,RenderError,RenderSettings};
+    use scene::Scene;
+    use plane::Plane;
+    use sphere::Sphere;
+    use light::Light;
+    use material::BasicMaterial;
+    use accelerator::AcceleratorKind;
+    use color::Color;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_blinn_phong() {
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let material = scene.add_material(BasicMaterial::glossy(Color::new(0.5, 0.5, 0.5), 0.25, 50.0));
+        scene.add_plane(Plane { material: Some(material), ..Plane::new(Point::zero(), Vector::new(0.0, 0.0, 1.0)) });
+        scene.add_light(Light::new(Point::new(0.0, 0.0, 10.0)));
+
+        // looking straight at the highlight
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let hit = scene.intersect(ray, 0.0, f64::INFINITY).unwrap();
+        let color = shade(&scene, ray, &hit);
+        assert!((color.r - 0.75).abs() < 1e-9);
+
+        // off to the side, the highlight has nearly faded but the diffuse
+        // part hasn't changed
+        let ray = Ray::new(Point::new(-5.0, 0.0, 5.0), Vector::new(1.0, 0.0, -1.0));
+        let hit = scene.intersect(ray, 0.0, f64::INFINITY).unwrap();
+        let color = shade(&scene, ray, &hit);
+        assert!(color.r > 0.5 && color.r < 0.51);
+    }
+
+    #[test]
+    fn test_no_shininess() {
+        // specular color but a shininess of 0, as from an MTL without `Ns`
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let rough = BasicMaterial { roughness: 1.0, ..BasicMaterial::glossy(Color::new(0.5, 0.5, 0.5), 0.25, 50.0) };
+        let material = scene.add_material(rough);
+        scene.add_plane(Plane { material: Some(material), ..Plane::new(Point::zero(), Vector::new(0.0, 0.0, 1.0)) });
+        scene.add_light(Light::new(Point::new(0.0, 0.0, 10.0)));
+
+        let ray = Ray::new(Point::new(-5.0, 0.0, 5.0), Vector::new(1.0, 0.0, -1.0));
+        let hit = scene.intersect(ray, 0.0, f64::INFINITY).unwrap();
+        let color = shade(&scene, ray, &hit);
+        assert!((color.r - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_leave() {
+        // far from the origin a fixed offset is lost in the rounding of the
+        // hit point's coordinates
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        scene.add_plane(Plane::new(Point::new(1e12, 1e12, 1e12), Vector::new(1.0, 2.0, 3.0)));
+        scene.build();
+
+        for i in 0..100 {
+            let ray = Ray::new(Point::new(1e12 + i as f64, 1e12, 1e12 + 1e6), Vector::new(0.3, 0.1, -1.0));
+            let hit = scene.intersect(ray, 0.0, f64::INFINITY).unwrap();
+            let bounce = leave(&hit, ray.dir.reflect(hit.normal));
+            assert!(scene.intersect(bounce, 0.0, f64::INFINITY).is_none());
+            let through = leave(&hit, ray.dir);
+            assert!(scene.intersect(through, 0.0, f64::INFINITY).is_none());
+        }
+    }
+
+    #[test]
+    fn test_no_camera() {
+        let scene = Scene::new(AcceleratorKind::Bvh);
+        let settings = RenderSettings { width: 4, height: 4, ..RenderSettings::default() };
+        assert!(render(scene, &settings).err() == Some(RenderError::NoCamera));
+    }
+
+    #[test]
+    fn test_schlick() {
+        assert!((schlick(1.0, 1.0, 1.5) - 0.04).abs() < 1e-9);
+        assert!((schlick(1.0, 1.5, 1.0) - 0.04).abs() < 1e-9);
+        assert!(schlick(0.0, 1.0, 1.5) == 1.0);
+        assert!(schlick(0.5, 1.5, 1.0) == 1.0);
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let mirror = scene.add_material(BasicMaterial::mirror(Color::new(0.5, 0.5, 0.5)));
+        scene.add_plane(Plane { material: Some(mirror), ..Plane::new(Point::zero(), Vector::new(0.0, 0.0, 1.0)) });
+        let red = scene.add_material(BasicMaterial { emission: Color::new(1.0, 0.0, 0.0), ..BasicMaterial::default() });
+        scene.add_sphere(Sphere { material: Some(red), ..Sphere::new(Point::new(5.0, 0.0, 5.0), 1.0) });
+        scene.build();
+        let settings = RenderSettings { background: Color::new(0.0, 0.0, 1.0), ..RenderSettings::default() };
+
+        // bounces off the mirror into the red sphere, or past it into the sky
+        let ray = Ray::new(Point::new(-5.0, 0.0, 5.0), Vector::new(1.0, 0.0, -1.0));
+        assert!(trace(&scene, ray, 0, &settings) == Color::new(0.5, 0.0, 0.0));
+        let ray = Ray::new(Point::new(-5.0, 3.0, 5.0), Vector::new(1.0, 0.0, -1.0));
+        assert!(trace(&scene, ray, 0, &settings) == Color::new(0.0, 0.0, 0.5));
+
+        // out of bounces
+        let settings = RenderSettings { max_depth: 0, ..settings };
+        assert!(trace(&scene, ray, 0, &settings) == Color::black());
+    }
+
+    #[test]
+    fn test_glass() {
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let glass = scene.add_material(BasicMaterial::glass(1.5));
+        scene.add_sphere(Sphere { material: Some(glass), ..Sphere::new(Point::zero(), 1.0) });
+        scene.build();
+        let settings = RenderSettings { background: Color::white(), ..RenderSettings::default() };
+
+        // nearly everything gets through head on, and what's reflected
+        // goes back to the sky
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let color = trace(&scene, ray, 0, &settings);
+        assert!(color.r > 0.95 && color.r <= 1.0);
+
+        // grazing the edge, most of it is reflected but some is lost
+        // bouncing around inside until it runs out of depth
+        let ray = Ray::new(Point::new(0.0, 0.999, 5.0), Vector::new(0.0, 0.0, -1.0));
+        let color = trace(&scene, ray, 0, &settings);
+        assert!(color.r > 0.8 && color.r <= 1.0);
+    }
+
+    #[test]
+    fn test_glass_energy() {
+        // MTL glass usually also has a white `Ks`, which becomes `reflection`
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let glass = scene.add_material(BasicMaterial { reflection: Color::white(), ..BasicMaterial::glass(1.5) });
+        scene.add_sphere(Sphere { material: Some(glass), ..Sphere::new(Point::zero(), 1.0) });
+        scene.build();
+        let settings = RenderSettings { background: Color::white(), ..RenderSettings::default() };
+
+        for i in 0..20 {
+            let ray = Ray::new(Point::new(0.0, i as f64 * 0.05, 5.0), Vector::new(0.0, 0.0, -1.0));
+            let color = trace(&scene, ray, 0, &settings);
+            assert!(color.r <= 1.0 + 1e-9);
+        }
+    }
 }
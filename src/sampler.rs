@@ -0,0 +1,71 @@
+/// Small xorshift generator used to jitter samples. Fast and
+/// deterministic for a given seed, but not suitable for anything else.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Sampler {
+    state: u64,
+}
+
+impl Sampler {
+
+    pub fn new(seed: u64) -> Sampler {
+        // xorshift gets stuck on zero, so mix the seed first
+        let state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+
+        Sampler {
+            state,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a point uniformly distributed on the unit disk.
+    pub fn disk(&mut self) -> (f64, f64) {
+        loop {
+            let x = self.next_f64() * 2.0 - 1.0;
+            let y = self.next_f64() * 2.0 - 1.0;
+
+            if x * x + y * y <= 1.0 {
+                return (x, y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sampler::Sampler;
+
+    #[test]
+    fn test_range() {
+        let mut sampler = Sampler::new(0);
+
+        for _ in 0..1000 {
+            let n = sampler.next_f64();
+            assert!((0.0..1.0).contains(&n));
+
+            let (x, y) = sampler.disk();
+            assert!(x * x + y * y <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Sampler::new(42);
+        let mut b = Sampler::new(42);
+        let mut c = Sampler::new(43);
+
+        let n = a.next_u64();
+        assert!(b.next_u64() == n);
+        assert!(c.next_u64() != n);
+    }
+}
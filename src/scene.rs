@@ -1,45 +1,456 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
 
-use triangle::Triangle;
+use mesh::Mesh;
+use material::{BasicMaterial,Material,TexturedMaterial};
+use mtl;
+use mtl::MtlMaterial;
+use texture::Texture;
+use sphere::Sphere;
+use shape::Shape;
+use instance::Instance;
+use plane::Plane;
+use light::Light;
+use obj;
+use obj::{Obj,ObjError};
+use accelerator;
+use accelerator::{Accelerator,AcceleratorKind};
+use camera::Camera;
+use geometry::{Bounded,Hit,Viewable};
+use bounds::Bounds;
 use point::Point;
-use octree::Octree;
-use camera::OrthoCamera;
+use vector::Vector;
+use ray::Ray;
+use transform::Transform;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LoadOptions {
+    /// Faces without normals get smooth ones averaged from neighbours in
+    /// the same smoothing group less than this many degrees apart. `None`
+    /// leaves them faceted.
+    pub smooth_angle: Option<f64>,
+    pub accelerator: AcceleratorKind,
+    /// Where to place the model in the scene.
+    pub transform: Transform,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            smooth_angle: Some(60.0),
+            accelerator: AcceleratorKind::Bvh,
+            transform: Transform::identity(),
+        }
+    }
+}
 
 pub struct Scene {
-    pub camera: Option<OrthoCamera>,
-    pub lights: Vec<Point>,
-    pub tree: Octree<Triangle>,
+    pub camera: Option<Box<dyn Camera>>,
+    pub lights: Vec<Light>,
+    /// Every bounded primitive, in the order they were added.
+    pub shapes: Vec<Shape>,
+    /// Unbounded planes, tested one by one next to `tree`.
+    pub planes: Vec<Plane>,
+    /// Looked up by the `material` of hits.
+    pub materials: Vec<Box<dyn Material>>,
+    /// Problems that didn't stop the scene from loading, like missing
+    /// material libraries.
+    pub warnings: Vec<String>,
+    /// Built from `shapes` by `build`.
+    pub tree: Box<dyn Accelerator>,
+    accelerator: AcceleratorKind,
+    fallback: BasicMaterial,
+    prims: usize,
+    dirty: bool,
 }
 
 impl Scene {
 
-    pub fn from_file(file: File) -> Scene {
-        let reader = BufReader::new(file);
-        let mut verts: Vec<Point> = vec![];
-        let mut objects: Vec<Triangle> = vec![];
+    /// An empty scene whose shapes will go in the given kind of
+    /// accelerator.
+    pub fn new(accelerator: AcceleratorKind) -> Scene {
+        Scene {
+            camera: None,
+            lights: vec![],
+            shapes: vec![],
+            planes: vec![],
+            materials: vec![],
+            warnings: vec![],
+            tree: accelerator::build::<Shape, _>(accelerator, vec![]),
+            accelerator,
+            fallback: BasicMaterial::default(),
+            prims: 0,
+            dirty: false,
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Scene, ObjError> {
+        Scene::open_with(path, &LoadOptions::default())
+    }
 
-        for line in reader.lines().map(|l| l.unwrap()) {
-            match line.chars().next().unwrap() {
-                'v' => verts.push(Point::from_str(&line)),
-                'f' => objects.push(Triangle::from_str(&line, &verts)),
-                _ => continue, // choosing not to parse other types
+    /// Loads an OBJ file along with the material libraries it names, which
+    /// are looked for next to it.
+    pub fn open_with<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Scene, ObjError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Scene::load_from(BufReader::new(File::open(path)?), options, dir)
+    }
+
+    pub fn from_file(file: File) -> Result<Scene, ObjError> {
+        Scene::load(BufReader::new(file), &LoadOptions::default())
+    }
+
+    /// Loads OBJ data, looking for its material libraries in the working
+    /// directory.
+    pub fn load<R: BufRead>(reader: R, options: &LoadOptions) -> Result<Scene, ObjError> {
+        Scene::load_from(reader, options, Path::new(""))
+    }
+
+    fn load_from<R: BufRead>(reader: R, options: &LoadOptions, dir: &Path) -> Result<Scene, ObjError> {
+        let mut obj = obj::parse(reader)?;
+
+        if let Some(angle) = options.smooth_angle {
+            obj.generate_normals(angle);
+        }
+
+        let mut scene = Scene::new(options.accelerator);
+        let library = scene.load_libraries(&obj, dir);
+        let mesh = scene.import_obj(&obj, &options.transform, Some(&library));
+        scene.add_mesh(mesh);
+        scene.build();
+        Ok(scene)
+    }
+
+    /// Reads every material library `obj` names from `dir`. Libraries that
+    /// can't be read are left out with a warning.
+    fn load_libraries(&mut self, obj: &Obj, dir: &Path) -> Vec<MtlMaterial> {
+        let mut materials = vec![];
+
+        for library in obj.libraries.iter() {
+            let path = dir.join(library);
+            match mtl::open(&path) {
+                Ok(library) => materials.extend(library),
+                Err(e) => self.warnings.push(format!("could not load {}: {}", path.display(), e)),
             }
         }
 
-        Scene {
-            camera: None,
-            lights: vec![],
-            tree: objects.into_iter().collect(),
+        materials
+    }
+
+    /// Builds a scene from already parsed OBJ data, using its vertex
+    /// normals and texture coordinates where every corner of a face has
+    /// them. Triangles are numbered in the order of `obj.faces`.
+    pub fn from_obj(obj: &Obj, accelerator: AcceleratorKind) -> Scene {
+        let mut scene = Scene::new(accelerator);
+        scene.add_obj(obj, &Transform::identity());
+        scene.build();
+        scene
+    }
+
+    /// Adds the triangles of `obj` placed by `transform`, like `from_obj`.
+    /// Each material it names gets a new default material in the scene.
+    pub fn add_obj(&mut self, obj: &Obj, transform: &Transform) {
+        let mesh = self.import_obj(obj, transform, None);
+        self.add_mesh(mesh);
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) {
+        let mesh = Arc::new(mesh);
+
+        for triangle in Mesh::triangles(&mesh, 0) {
+            self.add_shape(triangle);
+        }
+    }
+
+    /// Builds an accelerator over the triangles of `obj` on their own, to
+    /// be shared by any number of `add_instance` calls. Triangles are
+    /// numbered in the order of `obj.faces`.
+    pub fn prototype(&mut self, obj: &Obj) -> Arc<dyn Accelerator> {
+        let mesh = Arc::new(self.import_obj(obj, &Transform::identity(), None));
+        Arc::from(accelerator::build(self.accelerator, Mesh::triangles(&mesh, 0)))
+    }
+
+    /// Adds a copy of `object` placed by `transform`, without copying its
+    /// primitives.
+    pub fn add_instance(&mut self, object: &Arc<dyn Accelerator>, transform: Transform) {
+        self.add_shape(Instance::new(object.clone(), transform));
+    }
+
+    /// Like `add_instance`, but drawn entirely in the scene's `material`
+    /// whatever the object's own faces use.
+    pub fn add_instance_with_material(&mut self, object: &Arc<dyn Accelerator>, transform: Transform, material: usize) {
+        self.add_shape(Instance { material: Some(material), ..Instance::new(object.clone(), transform) });
+    }
+
+    /// Mesh of `obj` with its material indices pointing at new materials
+    /// of the scene, taken from `library` when given. Materials missing
+    /// from it are warned about and get a default one.
+    fn import_obj(&mut self, obj: &Obj, transform: &Transform, library: Option<&[MtlMaterial]>) -> Mesh {
+        let first = self.materials.len();
+        for name in obj.materials.iter() {
+            match library.map(|library| library.iter().find(|m| m.name == *name)) {
+                Some(Some(mtl)) => self.add_mtl(mtl),
+                Some(None) => {
+                    self.warnings.push(format!("material '{}' is not defined", name));
+                    self.add_material(BasicMaterial::default());
+                },
+                None => {
+                    self.add_material(BasicMaterial::default());
+                },
+            }
+        }
+
+        let mut mesh = Mesh::from_obj(obj, transform);
+        for face in mesh.faces.iter_mut() {
+            face.material = face.material.map(|m| first + m);
+        }
+
+        mesh
+    }
+
+    /// Adds an MTL material. Textures that can't be read, and bump maps,
+    /// are left out with a warning.
+    fn add_mtl(&mut self, mtl: &MtlMaterial) {
+        let base = mtl.to_basic();
+
+        if let Some(ref path) = mtl.bump_map {
+            self.warnings.push(format!("bump maps are not supported, ignoring {}", path.display()));
+        }
+
+        let texture = mtl.diffuse_map.as_ref().and_then(|path| match Texture::open(path) {
+            Ok(texture) => Some(texture),
+            Err(e) => {
+                self.warnings.push(format!("could not load {}: {}", path.display(), e));
+                None
+            },
+        });
+
+        match texture {
+            Some(texture) => self.add_material(TexturedMaterial::new(base, Arc::new(texture))),
+            None => self.add_material(base),
+        };
+    }
+
+    /// Adds a material, returning the index to refer to it by.
+    pub fn add_material<M: Material + 'static>(&mut self, material: M) -> usize {
+        self.materials.push(Box::new(material));
+        self.materials.len() - 1
+    }
+
+    /// The material with index `id`, or a plain white one if there is none.
+    pub fn material(&self, id: Option<usize>) -> &dyn Material {
+        match id.and_then(|id| self.materials.get(id)) {
+            Some(material) => &**material,
+            None => &self.fallback,
         }
     }
 
-    pub fn set_camera(&mut self, cam: OrthoCamera) {
-        self.camera = Some(cam);
+    pub fn set_camera<C: Camera + 'static>(&mut self, cam: C) {
+        self.camera = Some(Box::new(cam));
+    }
+
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    /// Adds a bounded primitive, numbering it after everything added
+    /// before. It isn't hit by rays until the next `build`.
+    pub fn add_shape<S: Into<Shape>>(&mut self, shape: S) {
+        let mut shape = shape.into();
+        shape.set_id(self.prims);
+        self.shapes.push(shape);
+        self.prims += 1;
+        self.dirty = true;
+    }
+
+    pub fn add_sphere(&mut self, sphere: Sphere) {
+        self.add_shape(sphere);
+    }
+
+    /// Adds a plane, numbered like `add_shape`.
+    pub fn add_plane(&mut self, plane: Plane) {
+        self.planes.push(Plane { id: self.prims, ..plane });
+        self.prims += 1;
+    }
+
+    /// Adds a floor facing up along z, just under everything else.
+    pub fn add_ground_plane(&mut self) {
+        let bounds = self.bounds();
+        let floor = Point::new(bounds.center().x, bounds.center().y, bounds.zmin);
+        self.add_plane(Plane::new(floor, Vector::new(0.0, 0.0, 1.0)));
+    }
+
+    /// Rebuilds the accelerator if shapes were added since the last build.
+    pub fn build(&mut self) {
+        if self.dirty {
+            self.tree = accelerator::build(self.accelerator, self.shapes.iter().cloned());
+            self.dirty = false;
+        }
+    }
+
+    /// Bounds of everything in the scene except its planes.
+    pub fn bounds(&self) -> Bounds {
+        match self.shapes.split_first() {
+            None => Bounds::zero(),
+            Some((first, rest)) => rest.iter().fold(first.bounds(), |sum, s| sum + s.bounds()),
+        }
+    }
+
+    /// Closest hit along `ray` between `t_min` and `t_max`.
+    pub fn intersect(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let closest = self.tree.intersect(ray, t_min, t_max);
+
+        self.planes.iter().fold(closest, |closest, plane| {
+            let t_max = closest.map_or(t_max, |hit| hit.t);
+            plane.intersects(ray, t_min, t_max).or(closest)
+        })
+    }
+
+    /// Whether anything blocks `ray` between `t_min` and `t_max`.
+    pub fn occluded(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        self.planes.iter().any(|plane| plane.intersects(ray, t_min, t_max).is_some())
+            || self.tree.occluded(ray, t_min, t_max)
     }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use image::{Rgb,RgbImage};
+
+    use scene::{LoadOptions,Scene};
+    use shape::Shape;
+    use material::BasicMaterial;
+    use color::Color;
+    use accelerator::AcceleratorKind;
+    use bounds::Bounds;
+    use obj;
+    use transform::Transform;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_transform() {
+        let data = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\nf 1//1 2//2 3//3\n";
+        let options = LoadOptions {
+            transform: Transform::scale(-1.0, 1.0, 1.0).then(&Transform::translate(Vector::new(0.0, 0.0, 2.0))),
+            ..LoadOptions::default()
+        };
+        let scene = Scene::load(data.as_bytes(), &options).unwrap();
+        let ray = Ray::new(Point::new(-0.25, 0.25, 5.0), Vector::new(0.0, 0.0, -1.0));
+
+        let hit = scene.intersect(ray, 0.0, f64::INFINITY).unwrap();
+        assert!(hit.t == 3.0);
+        assert!(hit.normal == Vector::new(0.0, 0.0, 1.0));
+        assert!(hit.shading == Vector::new(0.0, 0.0, 1.0));
+
+        // nothing left where the triangle used to be
+        let ray = Ray::new(Point::new(0.25, 0.25, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(scene.intersect(ray, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_instances() {
+        let obj = obj::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n".as_bytes()).unwrap();
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let monkey = scene.prototype(&obj);
+
+        for i in 0..10 {
+            scene.add_instance(&monkey, Transform::translate(Vector::new(i as f64 * 2.0, 0.0, 0.0)));
+        }
+        scene.build();
+
+        assert!(scene.bounds() == Bounds::new(0.0, 19.0, 0.0, 1.0, 0.0, 0.0));
+
+        let ray = Ray::new(Point::new(6.25, 0.25, 1.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(scene.intersect(ray, 0.0, f64::INFINITY).map(|hit| hit.prim) == Some(3));
+
+        let between = Ray::new(Point::new(7.5, 0.25, 1.0), Vector::new(0.0, 0.0, -1.0));
+        assert!(scene.intersect(between, 0.0, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_instance_material() {
+        let obj = obj::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n".as_bytes()).unwrap();
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let monkey = scene.prototype(&obj);
+        let green = scene.add_material(BasicMaterial::diffuse(Color::new(0.0, 1.0, 0.0)));
+
+        scene.add_instance(&monkey, Transform::identity());
+        scene.add_instance_with_material(&monkey, Transform::translate(Vector::new(2.0, 0.0, 0.0)), green);
+        scene.build();
+
+        let down = Vector::new(0.0, 0.0, -1.0);
+        let plain = scene.intersect(Ray::new(Point::new(0.25, 0.25, 1.0), down), 0.0, f64::INFINITY).unwrap();
+        let painted = scene.intersect(Ray::new(Point::new(2.25, 0.25, 1.0), down), 0.0, f64::INFINITY).unwrap();
+        assert!(plain.material.is_some() && plain.material != Some(green));
+        assert!(painted.material == Some(green));
+    }
+
+    #[test]
+    fn test_materials() {
+        let data = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 1 3 2\n";
+        let mut scene = Scene::new(AcceleratorKind::Bvh);
+        let green = scene.add_material(BasicMaterial::diffuse(Color::new(0.0, 1.0, 0.0)));
+        scene.add_obj(&obj::parse(data.as_bytes()).unwrap(), &Transform::identity());
+
+        // the obj's materials come after the ones already there
+        let faces: Vec<Option<usize>> = scene.shapes.iter().map(|shape| match *shape {
+            Shape::Mesh(ref m) => m.mesh.faces[m.face as usize].material,
+            _ => unreachable!(),
+        }).collect();
+        assert!(faces == vec![None, Some(1)]);
+        assert!(scene.materials.len() == 2);
+
+        assert!(scene.material(Some(green)).albedo((0.0, 0.0)) == Color::new(0.0, 1.0, 0.0));
+        assert!(scene.material(None).albedo((0.0, 0.0)) == Color::white());
+        assert!(scene.material(Some(5)).albedo((0.0, 0.0)) == Color::white());
+    }
+
+    #[test]
+    fn test_libraries() {
+        let dir = env::temp_dir().join(format!("raytracer-scene-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("model.obj"), "\
+mtllib model.mtl missing.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 0
+usemtl red
+f 1/1 2/1 3/1
+usemtl textured
+f 1/1 3/1 2/1
+usemtl unknown
+f 1/1 2/1 3/1
+").unwrap();
+        fs::write(dir.join("model.mtl"), "\
+newmtl red
+Kd 1 0 0
+newmtl textured
+Kd 0.5 0.5 0.5
+map_Kd green.png
+").unwrap();
+        RgbImage::from_pixel(1, 1, Rgb([0, 255, 0])).save(dir.join("green.png")).unwrap();
+
+        let scene = Scene::open_with(dir.join("model.obj"), &LoadOptions::default());
+        fs::remove_dir_all(&dir).unwrap();
+        let scene = scene.unwrap();
+
+        assert!(scene.materials.len() == 3);
+        assert!(scene.material(Some(0)).albedo((0.0, 0.0)) == Color::new(1.0, 0.0, 0.0));
+        assert!(scene.material(Some(1)).albedo((0.0, 0.0)) == Color::new(0.0, 0.5, 0.0));
+        assert!(scene.material(Some(2)).albedo((0.0, 0.0)) == Color::white());
 
-    pub fn add_light(&mut self, p: Point) {
-        self.lights.push(p);
+        assert!(scene.warnings.len() == 2);
+        assert!(scene.warnings[0].contains("missing.mtl"));
+        assert!(scene.warnings[1] == "material 'unknown' is not defined");
     }
 }
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use geometry::{Bounded,Hit,Viewable};
+use triangle::Triangle;
+use sphere::Sphere;
+use instance::Instance;
+use mesh::MeshTriangle;
+use bounds::Bounds;
+use ray::Ray;
+
+/// Any of the bounded primitives a scene can hold, so they can share one
+/// `Accelerator`. Each variant keeps its own intersection code. Everything
+/// but mesh triangles is behind an `Arc` to keep the enum as small as they
+/// are, and cheap to copy into the several leaves an accelerator may put a
+/// shape in.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Shape {
+    Triangle(Arc<Triangle>),
+    Mesh(MeshTriangle),
+    Sphere(Arc<Sphere>),
+    Instance(Arc<Instance>),
+}
+
+impl Shape {
+
+    pub fn id(&self) -> usize {
+        match *self {
+            Shape::Triangle(ref t) => t.id,
+            Shape::Mesh(ref m) => m.id,
+            Shape::Sphere(ref s) => s.id,
+            Shape::Instance(ref i) => i.id,
+        }
+    }
+
+    pub fn set_id(&mut self, id: usize) {
+        match *self {
+            Shape::Triangle(ref mut t) => Arc::make_mut(t).id = id,
+            Shape::Mesh(ref mut m) => m.id = id,
+            Shape::Sphere(ref mut s) => Arc::make_mut(s).id = id,
+            Shape::Instance(ref mut i) => Arc::make_mut(i).id = id,
+        }
+    }
+}
+
+impl From<Triangle> for Shape {
+    fn from(t: Triangle) -> Shape {
+        Shape::Triangle(Arc::new(t))
+    }
+}
+
+impl From<MeshTriangle> for Shape {
+    fn from(m: MeshTriangle) -> Shape {
+        Shape::Mesh(m)
+    }
+}
+
+impl From<Sphere> for Shape {
+    fn from(s: Sphere) -> Shape {
+        Shape::Sphere(Arc::new(s))
+    }
+}
+
+impl From<Instance> for Shape {
+    fn from(i: Instance) -> Shape {
+        Shape::Instance(Arc::new(i))
+    }
+}
+
+impl Bounded for Shape {
+    fn bounds(&self) -> Bounds {
+        match *self {
+            Shape::Triangle(ref t) => t.bounds(),
+            Shape::Mesh(ref m) => m.bounds(),
+            Shape::Sphere(ref s) => s.bounds(),
+            Shape::Instance(ref i) => i.bounds(),
+        }
+    }
+}
+
+impl Viewable for Shape {
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        match *self {
+            Shape::Triangle(ref t) => t.intersects(ray, t_min, t_max),
+            Shape::Mesh(ref m) => m.intersects(ray, t_min, t_max),
+            Shape::Sphere(ref s) => s.intersects(ray, t_min, t_max),
+            Shape::Instance(ref i) => i.intersects(ray, t_min, t_max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use shape::Shape;
+    use triangle::Triangle;
+    use sphere::Sphere;
+    use accelerator;
+    use accelerator::AcceleratorKind;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_mixed() {
+        let shapes = [
+            Shape::from(Triangle {
+                id: 0,
+                ..Triangle::new(Point::new(-1.0, -1.0, 0.0), Point::new(1.0, -1.0, 0.0), Point::new(0.0, 1.0, 0.0))
+            }),
+            Shape::from(Sphere {
+                id: 1,
+                ..Sphere::new(Point::new(0.0, 0.0, 2.0), 0.5)
+            }),
+        ];
+
+        for kind in [AcceleratorKind::Octree, AcceleratorKind::Bvh].iter() {
+            let tree = accelerator::build(*kind, shapes.iter().cloned());
+            let down = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+            let up = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+            assert!(tree.intersect(down, 0.0, f64::INFINITY).map(|h| (h.prim, h.t)) == Some((1, 2.5)));
+            assert!(tree.intersect(up, 0.0, f64::INFINITY).map(|h| (h.prim, h.t)) == Some((0, 5.0)));
+            assert!(!tree.occluded(up, 0.0, 4.0));
+        }
+    }
+
+    #[test]
+    fn test_clone_shares() {
+        // copies in several accelerator leaves point at the same sphere
+        let shape = Shape::from(Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0));
+        match (&shape, &shape.clone()) {
+            (Shape::Sphere(a), Shape::Sphere(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => panic!("clone changed the variant"),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use geometry::{Bounded,Hit,Viewable};
+use point::Point;
+use ray::Ray;
+use bounds::Bounds;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Sphere {
+    pub center: Point,
+    pub radius: f64,
+    pub id: usize,
+    pub material: Option<usize>,
+}
+
+impl Sphere {
+
+    pub fn new(center: Point, radius: f64) -> Sphere {
+        Sphere {
+            center,
+            radius,
+            id: 0,
+            material: None,
+        }
+    }
+}
+
+impl Bounded for Sphere {
+    fn bounds(&self) -> Bounds {
+        let (c, r) = (self.center, self.radius);
+        Bounds::new(c.x - r, c.x + r, c.y - r, c.y + r, c.z - r, c.z + r)
+    }
+}
+
+impl Viewable for Sphere {
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        // solve |loc + t * dir - center| = radius, dir being a unit vector
+        let oc = ray.loc.vector_to(self.center);
+        let b = oc.dot(ray.dir);
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let disc = b * b - c;
+
+        if disc < 0.0 {
+            return None;
+        }
+
+        let root = disc.sqrt();
+        let t = if -b - root >= t_min { -b - root } else { -b + root };
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let point = ray.loc.translate(ray.dir * t);
+        let normal = point.vector_to(self.center) / self.radius;
+
+        // longitude around z and latitude from the north pole
+        let u = 0.5 + normal.y.atan2(normal.x) / (2.0 * PI);
+        let v = normal.z.clamp(-1.0, 1.0).acos() / PI;
+
+        Some(Hit {
+            t,
+            point,
+            normal,
+            shading: normal,
+            uv: (u, v),
+            prim: self.id,
+            material: self.material,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sphere::Sphere;
+    use geometry::Viewable;
+    use point::Point;
+    use vector::Vector;
+    use ray::Ray;
+
+    #[test]
+    fn test_intersects() {
+        let sphere = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
+        let ray = Ray::new(Point::new(5.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0));
+
+        let hit = sphere.intersects(ray, 0.0, 100.0).unwrap();
+        assert!(hit.t == 4.0);
+        assert!(hit.point == Point::new(1.0, 0.0, 0.0));
+        assert!(hit.normal == Vector::new(1.0, 0.0, 0.0));
+        assert!(hit.uv == (0.5, 0.5));
+
+        // too far, or pointing away
+        assert!(sphere.intersects(ray, 0.0, 3.0).is_none());
+        let away = Ray::new(Point::new(5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(sphere.intersects(away, 0.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_from_inside() {
+        let sphere = Sphere::new(Point::new(0.0, 0.0, 0.0), 2.0);
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+
+        // the normal still points outwards
+        let hit = sphere.intersects(ray, 0.0, 100.0).unwrap();
+        assert!(hit.t == 2.0);
+        assert!(hit.normal == Vector::new(0.0, 0.0, 1.0));
+        assert!(hit.uv.1 == 0.0);
+    }
+}
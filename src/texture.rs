@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use image;
+use image::{ImageResult,RgbImage};
+
+use color::Color;
+
+/// An image to look colors up in by texture coordinates, stored as linear
+/// color.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Texture {
+
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Texture {
+        assert!(pixels.len() == (width * height) as usize);
+
+        Texture {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Decodes an 8-bit sRGB image.
+    pub fn from_rgb8(image: &RgbImage) -> Texture {
+        let (width, height) = image.dimensions();
+        let pixels = image.pixels().map(|p| Color::from_srgb8(p.data)).collect();
+
+        Texture::new(width, height, pixels)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Texture> {
+        Ok(Texture::from_rgb8(&image::open(path)?.to_rgb()))
+    }
+
+    /// Bilinearly filtered color at `(u, v)`, with `v` pointing up the
+    /// image and the texture repeating outside of `[0, 1]`.
+    pub fn sample(&self, uv: (f64, f64)) -> Color {
+        let x = uv.0 * self.width as f64 - 0.5;
+        let y = (1.0 - uv.1) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        self.texel(x0, y0) * ((1.0 - fx) * (1.0 - fy))
+            + self.texel(x0 + 1, y0) * (fx * (1.0 - fy))
+            + self.texel(x0, y0 + 1) * ((1.0 - fx) * fy)
+            + self.texel(x0 + 1, y0 + 1) * (fx * fy)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as u32;
+        let y = y.rem_euclid(self.height as i64) as u32;
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use texture::Texture;
+    use color::Color;
+
+    #[test]
+    fn test_sample() {
+        // red on the bottom row, blue on the top
+        let (red, blue) = (Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+        let texture = Texture::new(2, 2, vec![blue, blue, red, red]);
+
+        assert!(texture.sample((0.25, 0.25)) == red);
+        assert!(texture.sample((0.75, 0.75)) == blue);
+        assert!(texture.sample((0.25, 0.5)) == Color::new(0.5, 0.0, 0.5));
+
+        // wraps around
+        assert!(texture.sample((1.25, -0.75)) == red);
+    }
+}
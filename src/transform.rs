@@ -0,0 +1,283 @@
+use std::ops::Mul;
+
+use matrix::Matrix4;
+use point::Point;
+use vector::Vector;
+
+/// An affine transform, keeping its inverse alongside so normals and
+/// inverse mappings don't need a matrix inversion.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Transform {
+    pub m: Matrix4,
+    pub inv: Matrix4,
+}
+
+impl Transform {
+
+    pub fn new(m: Matrix4, inv: Matrix4) -> Transform {
+        Transform {
+            m,
+            inv,
+        }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(Matrix4::identity(), Matrix4::identity())
+    }
+
+    pub fn translate(v: Vector) -> Transform {
+        Transform::new(
+            Matrix4::new([
+                [1.0, 0.0, 0.0, v.x],
+                [0.0, 1.0, 0.0, v.y],
+                [0.0, 0.0, 1.0, v.z],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            Matrix4::new([
+                [1.0, 0.0, 0.0, -v.x],
+                [0.0, 1.0, 0.0, -v.y],
+                [0.0, 0.0, 1.0, -v.z],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+        )
+    }
+
+    /// Scales along each axis. None of the factors may be zero.
+    pub fn scale(x: f64, y: f64, z: f64) -> Transform {
+        Transform::new(
+            Matrix4::new([
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            Matrix4::new([
+                [1.0 / x, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / y, 0.0, 0.0],
+                [0.0, 0.0, 1.0 / z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+        )
+    }
+
+    /// Rotates `degrees` counter-clockwise around `axis`, looking down the
+    /// axis towards the origin.
+    pub fn rotate(axis: Vector, degrees: f64) -> Transform {
+        let a = axis.to_unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let m = Matrix4::new([
+            [
+                a.x * a.x + (1.0 - a.x * a.x) * cos,
+                a.x * a.y * (1.0 - cos) - a.z * sin,
+                a.x * a.z * (1.0 - cos) + a.y * sin,
+                0.0,
+            ],
+            [
+                a.x * a.y * (1.0 - cos) + a.z * sin,
+                a.y * a.y + (1.0 - a.y * a.y) * cos,
+                a.y * a.z * (1.0 - cos) - a.x * sin,
+                0.0,
+            ],
+            [
+                a.x * a.z * (1.0 - cos) - a.y * sin,
+                a.y * a.z * (1.0 - cos) + a.x * sin,
+                a.z * a.z + (1.0 - a.z * a.z) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        // rotations are orthogonal
+        Transform::new(m, m.transpose())
+    }
+
+    /// Places an object at `eye` facing `target`: its +y axis ends up
+    /// pointing at the target, +z as close to `up` as possible and +x to
+    /// the right.
+    pub fn look_at(eye: Point, target: Point, up: Vector) -> Transform {
+        let b = Basis::look_at(eye, target, up);
+        let rotate = Matrix4::new([
+            [b.right.x, b.dir.x, b.up.x, 0.0],
+            [b.right.y, b.dir.y, b.up.y, 0.0],
+            [b.right.z, b.dir.z, b.up.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let rotation = Transform::new(rotate, rotate.transpose());
+
+        rotation.then(&Transform::translate(eye.vector_to(Point::zero())))
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform::new(next.m * self.m, self.inv * next.inv)
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform::new(self.inv, self.m)
+    }
+
+    /// Whether the transform mirrors space, turning the winding of
+    /// triangles around.
+    pub fn swaps_handedness(&self) -> bool {
+        self.m.determinant3() < 0.0
+    }
+
+    pub fn point(&self, p: Point) -> Point {
+        let m = &self.m.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+
+        if w == 1.0 { Point::new(x, y, z) } else { Point::new(x / w, y / w, z / w) }
+    }
+
+    /// Transforms a direction, which translations don't affect.
+    pub fn vector(&self, v: Vector) -> Vector {
+        let m = &self.m.m;
+        Vector::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Transforms a surface normal with the inverse transpose, so it stays
+    /// perpendicular to the transformed surface. The result is a unit
+    /// vector.
+    pub fn normal(&self, n: Vector) -> Vector {
+        let m = &self.inv.m;
+        Vector::new(
+            m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+            m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+            m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+        ).to_unit()
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+/// `a * b` applies `b` first, like the matrices it's made of.
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, t: Transform) -> Transform {
+        t.then(&self)
+    }
+}
+
+/// Orthonormal frame looking along `dir`, e.g. a camera's.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Basis {
+    pub dir: Vector,
+    pub right: Vector,
+    pub up: Vector,
+}
+
+impl Basis {
+
+    /// Builds a frame looking along `dir`. `up` only needs to point roughly
+    /// upwards and is used to pick the roll of the frame; if it is parallel
+    /// to `dir` the closest world axis that isn't is used instead.
+    pub fn new(dir: Vector, up: Vector) -> Basis {
+        let dir = dir.to_unit();
+        let mut right = dir.cross(up);
+
+        if right.mag() < 1e-9 * up.mag() || up.mag() == 0.0 {
+            let axis = if dir.x.abs() < 0.9 {
+                Vector::new(1.0, 0.0, 0.0)
+            } else {
+                Vector::new(0.0, 1.0, 0.0)
+            };
+            right = dir.cross(axis);
+        }
+
+        let right = right.to_unit();
+
+        Basis {
+            dir,
+            right,
+            up: right.cross(dir),
+        }
+    }
+
+    pub fn look_at(eye: Point, target: Point, up: Vector) -> Basis {
+        Basis::new(target.vector_to(eye), up)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use transform::{Basis,Transform};
+    use point::Point;
+    use vector::Vector;
+
+    fn close(a: Point, b: Point) -> bool {
+        a.distance_to(b) < 1e-9
+    }
+
+    #[test]
+    fn test_points_and_vectors() {
+        let t = Transform::translate(Vector::new(1.0, 2.0, 3.0));
+        assert!(t.point(Point::new(1.0, 1.0, 1.0)) == Point::new(2.0, 3.0, 4.0));
+        assert!(t.vector(Vector::new(1.0, 1.0, 1.0)) == Vector::new(1.0, 1.0, 1.0));
+
+        let r = Transform::rotate(Vector::new(0.0, 0.0, 1.0), 90.0);
+        assert!(close(r.point(Point::new(1.0, 0.0, 0.0)), Point::new(0.0, 1.0, 0.0)));
+
+        // scale, then rotate, then move
+        let t = Transform::scale(2.0, 2.0, 2.0).then(&r).then(&t);
+        let p = t.point(Point::new(1.0, 0.0, 0.0));
+        assert!(close(p, Point::new(1.0, 4.0, 3.0)));
+        assert!(close(t.inverse().point(p), Point::new(1.0, 0.0, 0.0)));
+        let product = Transform::translate(Vector::new(1.0, 2.0, 3.0)) * r * Transform::scale(2.0, 2.0, 2.0);
+        assert!(close(product.point(Point::new(1.0, 0.0, 0.0)), p));
+    }
+
+    #[test]
+    fn test_normals() {
+        // squashing a 45 degree slope makes it flatter, so its normal
+        // should tip towards z
+        let t = Transform::scale(1.0, 1.0, 0.5);
+        let n = t.normal(Vector::new(1.0, 0.0, 1.0));
+        assert!((n.x - 1.0 / 5.0f64.sqrt()).abs() < 1e-9);
+        assert!((n.z - 2.0 / 5.0f64.sqrt()).abs() < 1e-9);
+
+        assert!(!t.swaps_handedness());
+        assert!(Transform::scale(-1.0, 1.0, 1.0).swaps_handedness());
+    }
+
+    #[test]
+    fn test_look_at() {
+        let t = Transform::look_at(Point::new(1.0, 1.0, 0.0), Point::new(1.0, 1.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert!(close(t.point(Point::zero()), Point::new(1.0, 1.0, 0.0)));
+        assert!(close(t.point(Point::new(0.0, 1.0, 0.0)), Point::new(1.0, 1.0, -1.0)));
+        assert!(close(t.point(Point::new(0.0, 0.0, 1.0)), Point::new(1.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn test_basis() {
+        let basis = Basis::new(Vector::new(0.0, -2.0, 0.0), Vector::new(0.0, 1.0, 1.0));
+
+        // up is orthonormalized against the view direction
+        assert!(basis.dir == Vector::new(0.0, -1.0, 0.0));
+        assert!(basis.up == Vector::new(0.0, 0.0, 1.0));
+        assert!(basis.right == Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_basis_straight_down() {
+        // looking along the up vector used to produce NaN rays
+        let basis = Basis::new(Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(basis.right.mag() > 0.999 && basis.up.mag() > 0.999);
+        assert!(basis.right.dot(basis.dir).abs() < 1e-9);
+        assert!(basis.up.dot(basis.dir).abs() < 1e-9);
+        assert!(basis.up.dot(basis.right).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,4 @@
-use std::str::FromStr;
-
-use geometry::{Bounded,Viewable};
+use geometry::{Bounded,Hit,Viewable};
 use point::Point;
 use vector::Vector;
 use ray::Ray;
@@ -12,6 +10,12 @@ pub struct Triangle {
     pub v1: Point,
     pub v2: Point,
     pub n: Vector,
+    /// Per-vertex normals, for smooth shading.
+    pub normals: Option<[Vector; 3]>,
+    /// Per-vertex texture coordinates.
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub id: usize,
+    pub material: Option<usize>,
 }
 
 impl Triangle {
@@ -22,22 +26,69 @@ impl Triangle {
         let n = e1.cross(e2).to_unit();
 
         Triangle {
-            v0: v0,
-            v1: v1,
-            v2: v2,
-            n: n,
+            v0,
+            v1,
+            v2,
+            n,
+            normals: None,
+            uvs: None,
+            id: 0,
+            material: None,
         }
     }
 
-    pub fn from_str(line: &str, verts: &Vec<Point>) -> Triangle {
-        let mut entries = line.split_whitespace();
-        entries.next();
+    pub fn with_normals(v0: Point, v1: Point, v2: Point, normals: [Vector; 3]) -> Triangle {
+        Triangle {
+            normals: Some(normals),
+            ..Triangle::new(v0, v1, v2)
+        }
+    }
 
-        let v0 = verts[usize::from_str(entries.next().unwrap()).unwrap() - 1];
-        let v1 = verts[usize::from_str(entries.next().unwrap()).unwrap() - 1];
-        let v2 = verts[usize::from_str(entries.next().unwrap()).unwrap() - 1];
+    /// Barycentric coordinates `(u, v)` of `p` on the triangle's plane,
+    /// the weights of `v1` and `v2` respectively.
+    pub fn barycentric(&self, p: Point) -> (f64, f64) {
+        let e1 = self.v1.vector_to(self.v0);
+        let e2 = self.v2.vector_to(self.v0);
+        let ep = p.vector_to(self.v0);
+
+        let d11 = e1.dot(e1);
+        let d12 = e1.dot(e2);
+        let d22 = e2.dot(e2);
+        let dp1 = ep.dot(e1);
+        let dp2 = ep.dot(e2);
+        let denom = d11 * d22 - d12 * d12;
+
+        (
+            (d22 * dp1 - d12 * dp2) / denom,
+            (d11 * dp2 - d12 * dp1) / denom,
+        )
+    }
+
+    /// Normal to shade `p` with, interpolated from the vertex normals when
+    /// the triangle has them.
+    pub fn normal_at(&self, p: Point) -> Vector {
+        let (u, v) = self.barycentric(p);
+        self.shading_normal(u, v)
+    }
 
-        Triangle::new(v0, v1, v2)
+    /// Same as `normal_at`, from barycentric coordinates.
+    pub fn shading_normal(&self, u: f64, v: f64) -> Vector {
+        match self.normals {
+            None => self.n,
+            Some([n0, n1, n2]) => (n0 * (1.0 - u - v) + n1 * u + n2 * v).to_unit(),
+        }
+    }
+
+    /// Texture coordinates at barycentric coordinates `(u, v)`, or just the
+    /// barycentric coordinates if the triangle has none.
+    pub fn uv(&self, u: f64, v: f64) -> (f64, f64) {
+        match self.uvs {
+            None => (u, v),
+            Some([t0, t1, t2]) => (
+                t0.0 * (1.0 - u - v) + t1.0 * u + t2.0 * v,
+                t0.1 * (1.0 - u - v) + t1.1 * u + t2.1 * v,
+            ),
+        }
     }
 }
 
@@ -55,42 +106,62 @@ impl Bounded for Triangle {
 }
 
 impl Viewable for Triangle {
-    fn intersects(&self, ray: Ray) -> Option<Point> {
-        let ev1 = self.v1.vector_to(self.v0);
-        let ev2 = self.v2.vector_to(self.v0);
-        let pvec = ray.dir.cross(ev2);
-        let det = ev1.dot(pvec);
-
-        if det > -0.0001 && det < 0.0001 {
-            return None;
-        }
+    fn intersects(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Hit> {
+        let (t, u, v) = moller_trumbore(self.v0, self.v1, self.v2, ray, t_min, t_max)?;
+
+        Some(Hit {
+            t,
+            point: ray.loc.translate(ray.dir * t),
+            normal: self.n,
+            shading: self.shading_normal(u, v),
+            uv: self.uv(u, v),
+            prim: self.id,
+            material: self.material,
+        })
+    }
+}
 
-        let invdet = 1.0 / det;
-        let tvec = ray.loc.vector_to(self.v0);
+/// Distance along `ray` to the triangle `v0 v1 v2` and the barycentric
+/// coordinates `(u, v)` of the hit, if it's between `t_min` and `t_max`.
+pub fn moller_trumbore(v0: Point, v1: Point, v2: Point, ray: Ray, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+    let ev1 = v1.vector_to(v0);
+    let ev2 = v2.vector_to(v0);
+    let pvec = ray.dir.cross(ev2);
+    let det = ev1.dot(pvec);
 
-        let u = tvec.dot(pvec) * invdet;
-        if u < 0.0 || u > 1.0  {
-            return None;
-        }
+    if det > -0.0001 && det < 0.0001 {
+        return None;
+    }
 
+    let invdet = 1.0 / det;
+    let tvec = ray.loc.vector_to(v0);
 
-        let qvec = tvec.cross(ev1);
-        let v = ray.dir.dot(qvec) * invdet;
-        if v < 0.0 || u + v > 1.0 {
-            return None;
-        }
+    let u = tvec.dot(pvec) * invdet;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
 
-        let t = ev2.dot(qvec) * invdet;
+    let qvec = tvec.cross(ev1);
+    let v = ray.dir.dot(qvec) * invdet;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
 
-        Some(ray.loc.translate(ray.dir * t))
+    let t = ev2.dot(qvec) * invdet;
+    if t < t_min || t > t_max {
+        return None;
     }
+
+    Some((t, u, v))
 }
 
 #[cfg(test)]
 mod test {
     use triangle::Triangle;
+    use geometry::Viewable;
     use point::Point;
     use vector::Vector;
+    use ray::Ray;
 
     #[test]
     fn test_create() {
@@ -109,4 +180,57 @@ mod test {
         // counter-clockwise vectors means the face is pointing towards us
         assert!(tri.n == Vector::new(0.0, 0.0, 1.0), "face is pointing up");
     }
+
+    #[test]
+    fn test_normal_at() {
+        let flat = Triangle::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0)
+        );
+        let smooth = Triangle::with_normals(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            [
+                Vector::new(0.0, 0.0, 1.0),
+                Vector::new(1.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ]
+        );
+
+        assert!(flat.normal_at(Point::new(0.5, 0.5, 0.0)) == Vector::new(0.0, 0.0, 1.0));
+        assert!(smooth.barycentric(Point::new(0.5, 0.25, 0.0)) == (0.5, 0.25));
+        assert!(smooth.normal_at(Point::new(1.0, 0.0, 0.0)) == Vector::new(1.0, 0.0, 0.0));
+        assert!(smooth.normal_at(Point::new(0.5, 0.5, 0.0)) == Vector::new(1.0, 1.0, 0.0).to_unit());
+    }
+
+    #[test]
+    fn test_intersects() {
+        let tri = Triangle {
+            id: 7,
+            uvs: Some([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]),
+            ..Triangle::new(
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0)
+            )
+        };
+        let ray = Ray::new(Point::new(0.5, 0.25, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        let hit = tri.intersects(ray, 0.0, 100.0).unwrap();
+        assert!(hit.t == 2.0);
+        assert!(hit.point == Point::new(0.5, 0.25, 0.0));
+        assert!(hit.normal == Vector::new(0.0, 0.0, 1.0));
+        assert!(hit.uv == (0.75, 0.25));
+        assert!(hit.prim == 7);
+
+        // outside of the interval
+        assert!(tri.intersects(ray, 0.0, 1.0).is_none());
+        assert!(tri.intersects(ray, 3.0, 100.0).is_none());
+
+        // behind the ray
+        let away = Ray::new(Point::new(0.5, 0.25, 2.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(tri.intersects(away, 0.0, 100.0).is_none());
+    }
 }
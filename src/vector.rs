@@ -10,9 +10,9 @@ pub struct Vector {
 impl Vector {
     pub fn new(x: f64, y: f64, z: f64) -> Vector {
         Vector {
-            x: x,
-            y: y,
-            z: z,
+            x,
+            y,
+            z,
         }
     }
 
@@ -47,6 +47,27 @@ impl Vector {
             + self.y * v.y
             + self.z * v.z
     }
+
+    /// Mirrors the vector about a surface with unit normal `n`.
+    pub fn reflect(self, n: Vector) -> Vector {
+        self - n * (2.0 * self.dot(n))
+    }
+
+    /// Bends a unit direction through a surface by Snell's law, where `n`
+    /// is the unit normal on the side the direction comes from and `eta` is
+    /// the ratio of the indices of refraction on either side. `None` means
+    /// total internal reflection.
+    pub fn refract(self, n: Vector, eta: f64) -> Option<Vector> {
+        let cos_i = -self.dot(n);
+        let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self * eta + n * (eta * cos_i - cos_t))
+    }
 }
 
 impl Add for Vector  {
@@ -156,6 +177,30 @@ mod test {
         assert!(Vector::new(1.0,1.0,1.0) * -0.5 == Vector::new(-0.5,-0.5,-0.5));
     }
 
+    #[test]
+    fn test_reflect() {
+        let n = Vector::new(0.0, 0.0, 1.0);
+        assert!(Vector::new(1.0, 0.0, -1.0).reflect(n) == Vector::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_refract() {
+        let n = Vector::new(0.0, 0.0, 1.0);
+        let d = Vector::new(1.0, 0.0, -1.0).to_unit();
+
+        // straight through when nothing changes, and head on
+        assert!(d.refract(n, 1.0) == Some(d));
+        assert!(Vector::new(0.0, 0.0, -1.0).refract(n, 1.5) == Some(Vector::new(0.0, 0.0, -1.0)));
+
+        // into a denser medium the ray bends towards the normal
+        let t = d.refract(n, 1.0 / 1.5).unwrap();
+        assert!((t.mag() - 1.0).abs() < 1e-9);
+        assert!((t.x - (0.5f64.sqrt() / 1.5)).abs() < 1e-9);
+
+        // and out of one at a shallow angle it can't get out at all
+        assert!(d.refract(n, 1.5).is_none());
+    }
+
     #[test]
     fn test_eq() {
         assert!(Vector::new(0.0,0.0,0.0) == Vector::new(0.0,0.0,0.0));